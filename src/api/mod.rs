@@ -1,11 +1,12 @@
 pub mod v1;
 
-pub fn routes() -> axum::Router
-{
-    let v1_routes = axum::Router::new()
-        .nest("/tags", v1::tags::routes())
-        .nest("/datasets", v1::datasets::routes())
-        .nest("/platforms", v1::platforms::routes());
+use axum::extract::Extension;
+
+use crate::backend::Backend;
 
-    axum::Router::new().nest("/v1", v1_routes)
+pub fn routes(backend: Backend) -> axum::Router
+{
+    axum::Router::new()
+        .nest("/v1", v1::routes())
+        .layer(Extension(backend))
 }
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, OriginalUri},
+    http::StatusCode,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::api::v1::{
+    extract::{Json, Path},
+    graphql::{
+        GetOneFactory,
+        ListFactory,
+    },
+    datasets::{query_values, PLATFORM_VALUES, TAGS_VALUES},
+    listing,
+    openapi::ApiRouter,
+    params::Listed,
+    tagging,
+};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::{Context, Mutation};
+use crate::error::{ApiError, Problem};
+use crate::schemas::{
+    self,
+    requests,
+    Charts,
+    ChartEnvelope,
+    Datasets,
+    Links,
+    Projection,
+    QueryResponse,
+};
+
+
+const PROPERTIES_VALUES: &str = "
+    properties {
+        name
+        description
+        url: externalUrl
+        class: type
+    }
+";
+
+// Charts hardly ever read from more than a handful of datasets, so their
+// inputs come back in one go rather than paged.
+const MAX_INPUTS: i32 = 1000;
+
+static QUERY_VALUES: Lazy<String> = Lazy::new(|| {
+    format!("urn __typename ... on Chart {{ {PROPERTIES_VALUES} {PLATFORM_VALUES} {TAGS_VALUES} }}")
+});
+
+static LIST: Lazy<ListFactory>          = Lazy::new(|| ListFactory::new("CHART", &QUERY_VALUES));
+static GET_BY_ID: Lazy<GetOneFactory>   = Lazy::new(|| GetOneFactory::new("chart", &QUERY_VALUES));
+
+/// The selection set for the datasets a chart reads from, which DataHub
+/// records as the chart's `Consumes` relationships.
+fn input_values(projection: &Projection) -> String
+{
+    format!(
+        "urn __typename ... on Chart {{ \
+            inputs: relationships(input: {{ types: [\"Consumes\"], direction: OUTGOING, start: 0, count: {MAX_INPUTS} }}) {{ \
+                total relationships {{ entity {{ {} }} }} \
+            }} \
+        }}",
+        query_values(projection)
+    )
+}
+
+#[derive(Deserialize)]
+struct ChartResponse<'a> {
+    #[serde(borrow)]
+    data: ChartData<'a>,
+}
+
+#[derive(Deserialize)]
+struct ChartData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::Chart<'a>>,
+}
+
+#[derive(Deserialize)]
+struct InputsResponse<'a> {
+    #[serde(borrow)]
+    data: InputsData<'a>,
+}
+
+#[derive(Deserialize)]
+struct InputsData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::ChartInputs<'a>>,
+}
+
+pub fn routes(api: ApiRouter) -> ApiRouter
+{
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_datasets_by_chart, _, _>(datasets_by_chart)
+        .route::<__path_add_tag, _, _>(add_tag)
+        .route::<__path_remove_tag, _, _>(remove_tag)
+}
+
+#[utoipa::path(
+    get,
+    path = "/charts/{id}",
+    tag = "charts",
+    summary = "Chart with the specified id",
+    params(("id" = String, Path, description = "Chart urn")),
+    responses(
+        (status = 200, description = "The chart", body = ChartEnvelope),
+        (status = 404, description = "No such chart", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_id(
+    Path(id): Path<String>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<ChartEnvelope>), ApiError>
+{
+    let body = GET_BY_ID.body(&id);
+    let (status, bytes) = cache.fetch(Route::Chart, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: ChartResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(chart) => Ok((status, ChartEnvelope::from(&chart).into())),
+        None => Err(ApiError::NotFound(id))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/charts",
+    tag = "charts",
+    summary = "All charts, or those matching a query or name",
+    params(
+        ("query" = Option<String>, Query, description = "Charts with any value like this"),
+        ("name" = Option<String>, Query, description = "Charts with names like this, up to limit (not paged)"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of charts, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of charts to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("platform" = Option<String>, Query, description = "Charts on any of these comma separated platforms"),
+        ("tags" = Option<String>, Query, description = "Charts with any (or with tags_mode=all, every one) of these comma separated tags"),
+        ("tags_mode" = Option<String>, Query, description = "How tags combine: any (default) or all"),
+        ("-platform" = Option<String>, Query, description = "Charts on none of these platforms"),
+        ("-tags" = Option<String>, Query, description = "Charts without these tags"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags (not with name)"),
+        ("sort" = Option<String>, Query, description = "name, or -name for descending order (default relevance)"),
+    ),
+    responses(
+        (status = 200, description = "A page of charts", body = Charts, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown, conflicting or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_query(
+    Listed(params, ..): Listed<Charts>,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<Charts>), ApiError>
+{
+    let (status, bytes) = listing::fetch(Route::Charts, &LIST, &params, &backend, &cache, &ctx).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let (links, charts) = listing::page::<Charts>(&uri, &params, &body);
+
+    Ok((status, links, charts.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/charts/{id}/datasets",
+    tag = "charts",
+    summary = "Datasets the specified chart reads from",
+    params(
+        ("id" = String, Path, description = "Chart urn"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "The chart's input datasets, not paged", body = Datasets),
+        (status = 400, description = "Unknown fields or aspects", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such chart", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn datasets_by_chart(
+    Path(id): Path<String>,
+    projection: Projection,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<Datasets>), ApiError>
+{
+    let factory = GetOneFactory::new("chart", &input_values(&projection));
+    let body = factory.body(&id);
+    let (status, bytes) = cache.fetch(Route::ChartDatasets, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: InputsResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(chart) => Ok((status, Datasets::from(&chart.inputs).project(projection).into())),
+        None => Err(ApiError::NotFound(id))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/charts/{id}/tags",
+    tag = "charts",
+    summary = "Add a tag to the specified chart",
+    params(("id" = String, Path, description = "Chart urn")),
+    request_body = requests::AddTag,
+    responses(
+        (status = 204, description = "The tag was added"),
+        (status = 400, description = "The body isn't a valid request", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't add the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn add_tag(
+    Path(id): Path<String>,
+    Json(payload): Json<requests::AddTag>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
+{
+    tagging::add(&id, &payload.tag, &[Route::Charts], &backend, &cache, &ctx).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/charts/{id}/tags/{tag_id}",
+    tag = "charts",
+    summary = "Remove a tag from the specified chart",
+    params(
+        ("id" = String, Path, description = "Chart urn"),
+        ("tag_id" = String, Path, description = "Tag urn"),
+    ),
+    responses(
+        (status = 204, description = "The tag was removed"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't remove the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn remove_tag(
+    Path((id, tag_id)): Path<(String, String)>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
+{
+    tagging::remove(&id, &tag_id, &[Route::Charts], &backend, &cache, &ctx).await
+}
@@ -0,0 +1,302 @@
+use axum::{
+    http::{HeaderValue, Uri},
+    response::{IntoResponseParts, ResponseParts},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hyper::body::Bytes;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api::v1::{graphql, params::{Page, MAX_WINDOW}, queries};
+use crate::backend::Backend;
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::schemas::{GraphQL, Links, QueryResponse};
+
+
+/// Where a scrolled page starts: how many results came before it and
+/// DataHub's id for carrying on from there. Only the export starts a scroll
+/// without an id; callers are handed cursors with one, as an opaque token.
+#[derive(Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(rename = "o")]
+    pub offset: i32,
+
+    #[serde(rename = "s")]
+    pub scroll_id: Option<String>,
+}
+
+impl Cursor {
+    pub fn start() -> Cursor
+    {
+        Cursor { offset: 0, scroll_id: None }
+    }
+
+    pub fn encode(&self) -> String
+    {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(token: &str) -> Option<Cursor>
+    {
+        let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+static SEEK: Lazy<String> = Lazy::new(queries::seek);
+
+/// Runs the search for a page. Search can't say where to carry on from, so
+/// when the next page is out of `offset`'s reach DataHub is also scrolled
+/// to the end of this one, and its scroll id added to the response for
+/// the next link.
+pub async fn search(backend: &Backend, ctx: &Context, page: &Page, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+{
+    let bytes = backend.search(ctx, body).await?;
+    if page.cursor.is_some() {
+        return Ok(bytes)
+    }
+
+    let extent = serde_json::from_slice::<QueryResponse>(&bytes)?.extent();
+    let seek = match (extent, graphql::seek(&SEEK, body)) {
+        (Some((count, total)), Some(seek)) if beyond_offsets(page, count, total) => seek,
+        _ => return Ok(bytes)
+    };
+    let scrolled: Value = serde_json::from_slice(&backend.search(ctx, &seek).await?)?;
+    let scroll_id = match scrolled.pointer("/data/results/next") {
+        Some(Value::String(id)) => id.clone(),
+        _ => return Ok(bytes)
+    };
+
+    let mut resp: Value = serde_json::from_slice(&bytes)?;
+    if let Some(Value::Object(results)) = resp.pointer_mut("/data/results") {
+        results.insert("next".into(), scroll_id.into());
+    }
+    Ok(Bytes::from(serde_json::to_vec(&resp)?))
+}
+
+// Whether the results go on past a searched page that `offset` can't
+// follow, either because the next page would leave the search window or
+// because this first page shows there are more results than it holds.
+fn beyond_offsets(page: &Page, count: i32, total: i32) -> bool
+{
+    let end = page.start + count;
+    end < total && (end + page.limit > MAX_WINDOW || (page.start == 0 && total > MAX_WINDOW))
+}
+
+/// The pages either side of `page`. Searched pages link on with `offset`
+/// until the next page would leave the search window, and with a cursor
+/// holding DataHub's scroll id from there or for a listing longer than
+/// the window, as do scrolled pages. DataHub's scroll only runs forwards,
+/// so going back always uses `offset`.
+pub fn links(uri: &Uri, page: &Page, resp: &QueryResponse) -> Links
+{
+    let (count, total) = match resp.extent() {
+        Some(extent) => extent,
+        None => return Links::default()
+    };
+    let end = page.start + count;
+
+    let next = match resp.scroll_id() {
+        _ if end >= total => None,
+        Some(scroll_id) => {
+            let cursor = Cursor { offset: end, scroll_id: Some(scroll_id.to_owned()) };
+            Some(with(uri, "cursor", &cursor.encode()))
+        },
+        None if page.cursor.is_none() && end + page.limit <= MAX_WINDOW => {
+            Some(with(uri, "offset", &end.to_string()))
+        },
+        None => None
+    };
+
+    Links { offset: page.start, next, prev: prev(uri, page) }
+}
+
+/// The pages either side of a page of `count` out of `total` related
+/// entities, which only page by `offset`.
+pub fn offset_links(uri: &Uri, page: &Page, count: i32, total: i32) -> Links
+{
+    let end = page.start + count;
+    let next = (end < total && end + page.limit <= MAX_WINDOW)
+        .then(|| with(uri, "offset", &end.to_string()));
+
+    Links { offset: page.start, next, prev: prev(uri, page) }
+}
+
+fn prev(uri: &Uri, page: &Page) -> Option<String>
+{
+    let before = (page.start - page.limit).max(0);
+    if page.start > 0 && before + page.limit <= MAX_WINDOW {
+        Some(with(uri, "offset", &before.to_string()))
+    } else {
+        None
+    }
+}
+
+// The request's own path and query, with the page position replaced.
+fn with(uri: &Uri, key: &str, value: &str) -> String
+{
+    let pairs = form_urlencoded::parse(uri.query().unwrap_or("").as_bytes())
+        .filter(|(k, _)| k != "cursor" && k != "offset");
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .append_pair(key, value)
+        .finish();
+
+    format!("{}?{query}", uri.path())
+}
+
+impl IntoResponseParts for Links {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error>
+    {
+        let links: Vec<_> = [("next", &self.next), ("prev", &self.prev)].into_iter()
+            .filter_map(|(rel, url)| url.as_ref().map(|url| format!("<{url}>; rel=\"{rel}\"")))
+            .collect();
+        if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+            if !links.is_empty() {
+                res.headers_mut().insert("Link", value);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{extract::{FromRequest, RequestParts}, http::Request};
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::v1::graphql::GetAllFactory;
+    use crate::backend::MemoryBackend;
+
+
+    fn tags(n: usize) -> Backend
+    {
+        let tags = (0..n)
+            .map(|i| json!({ "__typename": "Tag", "urn": format!("urn:li:tag:t{i:05}") }))
+            .collect();
+        Arc::new(MemoryBackend::new(tags))
+    }
+
+    async fn page(query: &str) -> Result<Page, ApiError>
+    {
+        let req = Request::builder().uri(format!("/tags?{query}")).body(()).expect("valid request");
+        Page::from_request(&mut RequestParts::new(req)).await
+    }
+
+    // The first urn on the page and the links either side of it.
+    async fn listed(backend: &Backend, query: &str) -> (String, Links)
+    {
+        let uri: Uri = format!("/tags?{query}").parse().expect("valid uri");
+        let page = page(query).await.unwrap_or_else(|_| panic!("valid page {query}"));
+        let factory = GetAllFactory::new("TAG", "urn");
+        let body = factory.body(&page, &[]);
+
+        let bytes = search(backend, &Context::default(), &page, &body).await.expect("results");
+        let resp: QueryResponse = serde_json::from_slice(&bytes).expect("query response");
+        let first: Value = serde_json::from_slice(&bytes).expect("json");
+        let first = first.pointer("/data/results/entities/0/entity/urn").and_then(Value::as_str).unwrap_or_default();
+
+        (first.to_owned(), links(&uri, &page, &resp))
+    }
+
+    fn cursor(link: &Option<String>) -> Cursor
+    {
+        let link = link.as_deref().expect("next link");
+        let token = link.split_once("cursor=").map(|(_, token)| token).expect("cursor link");
+        Cursor::decode(token).expect("decodable cursor")
+    }
+
+    #[test]
+    fn cursors_round_trip()
+    {
+        let token = Cursor { offset: 10_000, scroll_id: Some("c2Nyb2xs".into()) }.encode();
+        let cursor = Cursor::decode(&token).expect("decodable cursor");
+        assert_eq!(cursor.offset, 10_000);
+        assert_eq!(cursor.scroll_id.as_deref(), Some("c2Nyb2xs"));
+    }
+
+    #[tokio::test]
+    async fn tampered_cursors_are_rejected()
+    {
+        let token = Cursor { offset: 10, scroll_id: Some("10".into()) }.encode();
+        assert!(page(&format!("cursor={token}")).await.is_ok());
+
+        let truncated = &token[..token.len() - 2];
+        let unscrolled = Cursor { offset: 10, scroll_id: None }.encode();
+        let negative = Cursor { offset: -10, scroll_id: Some("10".into()) }.encode();
+        let not_json = URL_SAFE_NO_PAD.encode("offset=10");
+        for token in [truncated, &unscrolled, &negative, &not_json, "not a cursor"] {
+            let query = form_urlencoded::Serializer::new(String::new()).append_pair("cursor", token).finish();
+            match page(&query).await {
+                Err(ApiError::InvalidParams(params)) => assert_eq!(params[0].name, "cursor"),
+                _ => panic!("accepted the cursor {token}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn searched_pages_link_by_offset_inside_the_window()
+    {
+        let backend = tags(50);
+
+        let (first, links) = listed(&backend, "limit=10").await;
+        assert_eq!(first, "urn:li:tag:t00000");
+        assert_eq!(links.next.as_deref(), Some("/tags?limit=10&offset=10"));
+        assert_eq!(links.prev, None);
+
+        let (_, links) = listed(&backend, "limit=10&offset=20").await;
+        assert_eq!(links.next.as_deref(), Some("/tags?limit=10&offset=30"));
+        assert_eq!(links.prev.as_deref(), Some("/tags?limit=10&offset=10"));
+
+        let (_, links) = listed(&backend, "limit=10&offset=40").await;
+        assert_eq!(links.next, None);
+    }
+
+    #[tokio::test]
+    async fn pages_at_the_window_edge_link_on_with_a_scroll_cursor()
+    {
+        let backend = tags(MAX_WINDOW as usize + 25);
+
+        let (_, links) = listed(&backend, "limit=10&offset=9980").await;
+        assert_eq!(links.next.as_deref(), Some("/tags?limit=10&offset=9990"));
+
+        let (first, links) = listed(&backend, "limit=10&offset=9990").await;
+        assert_eq!(first, "urn:li:tag:t09990");
+        assert_eq!(links.prev.as_deref(), Some("/tags?limit=10&offset=9980"));
+        let next = cursor(&links.next);
+        assert_eq!(next.offset, MAX_WINDOW);
+        assert_eq!(next.scroll_id.as_deref(), Some("10000"));
+
+        let (first, links) = listed(&backend, &format!("limit=10&cursor={}", next.encode())).await;
+        assert_eq!(first, "urn:li:tag:t10000");
+        assert_eq!(links.prev.as_deref(), Some("/tags?limit=10&offset=9990"));
+        let next = cursor(&links.next);
+        assert_eq!((next.offset, next.scroll_id.as_deref()), (10_010, Some("10010")));
+
+        let (first, links) = listed(&backend, &format!("limit=10&cursor={}", next.encode())).await;
+        assert_eq!(first, "urn:li:tag:t10010");
+        let next = cursor(&links.next);
+        let (first, links) = listed(&backend, &format!("limit=10&cursor={}", next.encode())).await;
+        assert_eq!(first, "urn:li:tag:t10020");
+        assert_eq!(links.next, None);
+    }
+
+    #[tokio::test]
+    async fn first_pages_beyond_the_window_link_on_with_a_scroll_cursor()
+    {
+        let backend = tags(MAX_WINDOW as usize + 1);
+
+        let (_, links) = listed(&backend, "limit=10").await;
+        let next = cursor(&links.next);
+        assert_eq!((next.offset, next.scroll_id.as_deref()), (10, Some("10")));
+        assert_eq!(links.prev, None);
+    }
+}
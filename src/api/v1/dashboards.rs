@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, OriginalUri},
+    http::StatusCode,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::api::v1::{
+    extract::{Json, Path},
+    graphql::{
+        GetOneFactory,
+        ListFactory,
+    },
+    datasets::{PLATFORM_VALUES, TAGS_VALUES},
+    listing,
+    openapi::ApiRouter,
+    params::Listed,
+    tagging,
+};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::{Context, Mutation};
+use crate::error::{ApiError, Problem};
+use crate::schemas::{
+    self,
+    requests,
+    Dashboards,
+    DashboardEnvelope,
+    Links,
+    QueryResponse,
+};
+
+
+const PROPERTIES_VALUES: &str = "
+    properties {
+        name
+        description
+        url: externalUrl
+    }
+";
+
+static QUERY_VALUES: Lazy<String> = Lazy::new(|| {
+    format!("urn __typename ... on Dashboard {{ {PROPERTIES_VALUES} {PLATFORM_VALUES} {TAGS_VALUES} }}")
+});
+
+static LIST: Lazy<ListFactory>          = Lazy::new(|| ListFactory::new("DASHBOARD", &QUERY_VALUES));
+static GET_BY_ID: Lazy<GetOneFactory>   = Lazy::new(|| GetOneFactory::new("dashboard", &QUERY_VALUES));
+
+#[derive(Deserialize)]
+struct DashboardResponse<'a> {
+    #[serde(borrow)]
+    data: DashboardData<'a>,
+}
+
+#[derive(Deserialize)]
+struct DashboardData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::Dashboard<'a>>,
+}
+
+pub fn routes(api: ApiRouter) -> ApiRouter
+{
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_add_tag, _, _>(add_tag)
+        .route::<__path_remove_tag, _, _>(remove_tag)
+}
+
+#[utoipa::path(
+    get,
+    path = "/dashboards/{id}",
+    tag = "dashboards",
+    summary = "Dashboard with the specified id",
+    params(("id" = String, Path, description = "Dashboard urn")),
+    responses(
+        (status = 200, description = "The dashboard", body = DashboardEnvelope),
+        (status = 404, description = "No such dashboard", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_id(
+    Path(id): Path<String>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<DashboardEnvelope>), ApiError>
+{
+    let body = GET_BY_ID.body(&id);
+    let (status, bytes) = cache.fetch(Route::Dashboard, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: DashboardResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(dashboard) => Ok((status, DashboardEnvelope::from(&dashboard).into())),
+        None => Err(ApiError::NotFound(id))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/dashboards",
+    tag = "dashboards",
+    summary = "All dashboards, or those matching a query or name",
+    params(
+        ("query" = Option<String>, Query, description = "Dashboards with any value like this"),
+        ("name" = Option<String>, Query, description = "Dashboards with names like this, up to limit (not paged)"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of dashboards, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of dashboards to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("platform" = Option<String>, Query, description = "Dashboards on any of these comma separated platforms"),
+        ("tags" = Option<String>, Query, description = "Dashboards with any (or with tags_mode=all, every one) of these comma separated tags"),
+        ("tags_mode" = Option<String>, Query, description = "How tags combine: any (default) or all"),
+        ("-platform" = Option<String>, Query, description = "Dashboards on none of these platforms"),
+        ("-tags" = Option<String>, Query, description = "Dashboards without these tags"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags (not with name)"),
+        ("sort" = Option<String>, Query, description = "name, or -name for descending order (default relevance)"),
+    ),
+    responses(
+        (status = 200, description = "A page of dashboards", body = Dashboards, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown, conflicting or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_query(
+    Listed(params, ..): Listed<Dashboards>,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<Dashboards>), ApiError>
+{
+    let (status, bytes) = listing::fetch(Route::Dashboards, &LIST, &params, &backend, &cache, &ctx).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let (links, dashboards) = listing::page::<Dashboards>(&uri, &params, &body);
+
+    Ok((status, links, dashboards.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/dashboards/{id}/tags",
+    tag = "dashboards",
+    summary = "Add a tag to the specified dashboard",
+    params(("id" = String, Path, description = "Dashboard urn")),
+    request_body = requests::AddTag,
+    responses(
+        (status = 204, description = "The tag was added"),
+        (status = 400, description = "The body isn't a valid request", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't add the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn add_tag(
+    Path(id): Path<String>,
+    Json(payload): Json<requests::AddTag>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
+{
+    tagging::add(&id, &payload.tag, &[Route::Dashboards], &backend, &cache, &ctx).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/dashboards/{id}/tags/{tag_id}",
+    tag = "dashboards",
+    summary = "Remove a tag from the specified dashboard",
+    params(
+        ("id" = String, Path, description = "Dashboard urn"),
+        ("tag_id" = String, Path, description = "Tag urn"),
+    ),
+    responses(
+        (status = 204, description = "The tag was removed"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't remove the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn remove_tag(
+    Path((id, tag_id)): Path<(String, String)>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
+{
+    tagging::remove(&id, &tag_id, &[Route::Dashboards], &backend, &cache, &ctx).await
+}
@@ -1,85 +1,143 @@
+use std::sync::Arc;
+
 use axum::{
-    Json, Router,
-    extract::{Extension, Path},
-    http::{Request, StatusCode},
-    routing::{get, post, delete}
+    body::{self, Body},
+    extract::{Extension, OriginalUri},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
 };
-use hyper::{client::HttpConnector, Body};
-use once_cell::sync::Lazy;
+use hyper::body::Bytes;
 use serde::Deserialize;
+use tracing::Instrument;
 
 use crate::api::v1::{
+    extract::{Json, Path},
     graphql::{
-        AddTagFactory,
         GetAllFactory,
         GetOneFactory,
+        ListFactory,
         QueryFactory,
-        NameFactory,
-        RemoveTagFactory,
-        TagsFactory,
+        RelatedFactory,
+        SearchFactory,
     },
-    params::{QueryParams, QueryType}
+    cursor::{self, Cursor},
+    listing,
+    openapi::ApiRouter,
+    params::{ExportParams, Filtered, Page, RelatedParams, SearchParams},
+    pipelines::{job_values, jobs_page},
+    report,
+    tagging,
 };
-use crate::datahub::{self, GRAPHQL_ENDPOINT};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::{Context, Mutation};
+use crate::error::{ApiError, Problem};
 use crate::schemas::{
     self,
     requests,
+    Collection,
     Datasets,
     DatasetEnvelope,
-    DatasetAddTagResponse,
+    Jobs,
+    Links,
+    Projection,
     QueryResponse,
 };
 
 
-pub const QUERY_VALUES: &str = "
-    urn
-    __typename
-    ... on Dataset {
+pub(super) const PLATFORM_VALUES: &str = "
+    platform {
         name
-        platform {
-            name
-            properties {
-                name: displayName
-                class: type
-            }
-        }
         properties {
-            name
-            origin
-        }
-        schema: schemaMetadata {
-            fields {
-                path: fieldPath
-                class: type
-                native: nativeDataType
-            }
+            name: displayName
+            class: type
         }
-        sub_types: subTypes {
-            names: typeNames
+    }
+";
+
+const PROPERTIES_VALUES: &str = "
+    properties {
+        name
+        origin
+    }
+";
+
+const SCHEMA_VALUES: &str = "
+    schema: schemaMetadata {
+        fields {
+            path: fieldPath
+            class: type
+            native: nativeDataType
         }
+    }
+";
+
+const SUB_TYPES_VALUES: &str = "
+    sub_types: subTypes {
+        names: typeNames
+    }
+";
+
+pub(super) const TAGS_VALUES: &str = "
+    tags {
         tags {
-            tags {
-                tag {
-                    urn
-                    properties {
-                        name
-                        description
-                    }
+            tag {
+                urn
+                properties {
+                    name
+                    description
                 }
             }
         }
     }
 ";
 
-static ADD_TAG: Lazy<AddTagFactory>       = Lazy::new(|| AddTagFactory::new());
-static REMOVE_TAG: Lazy<RemoveTagFactory> = Lazy::new(|| RemoveTagFactory::new());
-static GET_ALL: Lazy<GetAllFactory>       = Lazy::new(|| GetAllFactory::new("DATASET", QUERY_VALUES));
-static GET_BY_ID: Lazy<GetOneFactory>     = Lazy::new(|| GetOneFactory::new("dataset", QUERY_VALUES));
-static GET_BY_NAME: Lazy<NameFactory>     = Lazy::new(|| NameFactory::new("DATASET", QUERY_VALUES));
-static GET_BY_TAGS: Lazy<TagsFactory>     = Lazy::new(|| TagsFactory::new("DATASET", QUERY_VALUES));
-static GET_BY_QUERY: Lazy<QueryFactory>   = Lazy::new(|| QueryFactory::new("DATASET", QUERY_VALUES));
+/// The selection set for datasets, asking DataHub for only the aspects
+/// the projection's fields come from.
+pub fn query_values(projection: &Projection) -> String
+{
+    let wants = |keys: &[&str]| keys.iter().any(|key| projection.wants(key));
+    let aspects = [
+        (wants(&["name", "origin"]), PROPERTIES_VALUES),
+        (wants(&["platform", "platformType", "platformName"]), PLATFORM_VALUES),
+        (wants(&["type"]), SUB_TYPES_VALUES),
+        (wants(&["fields"]), SCHEMA_VALUES),
+        (wants(&["tags"]), TAGS_VALUES),
+    ];
+    let values: String = aspects.iter()
+        .filter(|(wanted, _)| *wanted)
+        .map(|(_, values)| *values)
+        .collect();
 
-type Client = hyper::client::Client<HttpConnector, Body>;
+    format!("urn __typename ... on Dataset {{ name {values} }}")
+}
+
+// How many datasets an export asks DataHub for at a time, which bounds the
+// memory a stream holds on to.
+const EXPORT_BATCH: i32 = 100;
+
+// Dataset listings that may include the (un)tagged dataset. The dataset's
+// own entry and the tag's dataset listing are found by their urns, so the
+// single-entity routes needn't be dropped wholesale.
+const TAGGING_ROUTES: &[Route] = &[
+    Route::Datasets, Route::DatasetJobs, Route::PlatformDatasets,
+    Route::ChartDatasets, Route::PipelineJobs,
+];
+
+/// The selection set for a page of the jobs reading or writing a dataset,
+/// which DataHub records as `Consumes` and `Produces` relationships from
+/// each job to the dataset.
+fn dataset_job_values(projection: &Projection) -> String
+{
+    format!(
+        "urn __typename ... on Dataset {{ \
+            jobs: relationships(input: {{ types: [\"Consumes\", \"Produces\"], direction: INCOMING, start: $start, count: $count }}) {{ \
+                total relationships {{ entity {{ {} }} }} \
+            }} \
+        }}",
+        job_values(projection)
+    )
+}
 
 #[derive(Deserialize)]
 struct DatasetResponse<'a> {
@@ -87,101 +145,397 @@ struct DatasetResponse<'a> {
     data: schemas::datahub::DatasetEntity<'a>,
 }
 
-pub fn routes() -> Router
+#[derive(Deserialize)]
+struct JobsResponse<'a> {
+    #[serde(borrow)]
+    data: JobsData<'a>,
+}
+
+#[derive(Deserialize)]
+struct JobsData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::RelatedJobs<'a>>,
+}
+
+pub fn routes(api: ApiRouter) -> ApiRouter
 {
-    Router::new()
-        .route("/", get(by_query))
-        .route("/:id", get(by_id))
-        .route("/:id/tags", post(add_tag))
-        .route("/:id/tags/:tag_id", delete(remove_tag))
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_search, _, _>(search)
+        .route::<__path_export, _, _>(export)
+        .route::<__path_explain, _, _>(explain)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_jobs_by_dataset, _, _>(jobs_by_dataset)
+        .route::<__path_add_tag, _, _>(add_tag)
+        .route::<__path_remove_tag, _, _>(remove_tag)
 }
 
+#[utoipa::path(
+    get,
+    path = "/datasets/{id}",
+    tag = "datasets",
+    summary = "Dataset with the specified id",
+    params(
+        ("id" = String, Path, description = "Dataset urn"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "The dataset", body = DatasetEnvelope),
+        (status = 400, description = "Unknown fields or aspects", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such dataset", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_id(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-) -> Json<DatasetEnvelope>
+    projection: Projection,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<DatasetEnvelope>), ApiError>
+{
+    let factory = GetOneFactory::new("dataset", &query_values(&projection));
+    let body = factory.body(&id);
+    let (status, bytes) = cache.fetch(Route::Dataset, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: DatasetResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(..) => Ok((status, DatasetEnvelope::from(&body.data).project(projection).into())),
+        None => Err(ApiError::NotFound(id))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/datasets/{id}/jobs",
+    tag = "datasets",
+    summary = "Jobs reading or writing the specified dataset, with their input and output datasets",
+    params(
+        ("id" = String, Path, description = "Dataset urn"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of jobs, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of jobs to skip (default 0); offset plus limit may not exceed 10000"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields of the input and output datasets to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated dataset aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "A page of the dataset's jobs", body = Jobs, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such dataset", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn jobs_by_dataset(
+    Path(id): Path<String>,
+    params: RelatedParams,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<Jobs>), ApiError>
 {
-    let body = GET_BY_ID.body(&id);
-    let resp = datahub::post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: DatasetResponse = serde_json::from_slice(&bytes).unwrap();
+    let factory = RelatedFactory::new("dataset", &dataset_job_values(&params.projection));
+    let body = factory.body(&id, &params.page);
+    let (status, bytes) = cache.fetch(Route::DatasetJobs, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: JobsResponse = serde_json::from_slice(&bytes)?;
 
-    DatasetEnvelope::from(&body.data).into()
+    match body.data.entity {
+        Some(dataset) => {
+            let (links, jobs) = jobs_page(&uri, &params, &dataset.jobs);
+            Ok((status, links, jobs.into()))
+        },
+        None => Err(ApiError::NotFound(id))
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/datasets",
+    tag = "datasets",
+    summary = "All datasets, or those matching a query or name, optionally filtered",
+    params(
+        ("query" = Option<String>, Query, description = "Datasets with any value like this"),
+        ("name" = Option<String>, Query, description = "Datasets with names like this, up to limit (not paged, no filters)"),
+        ("platform" = Option<String>, Query, description = "Datasets on any of these comma separated platforms"),
+        ("tags" = Option<String>, Query, description = "Datasets with any (or with tags_mode=all, every one) of these comma separated tags"),
+        ("tags_mode" = Option<String>, Query, description = "How tags combine: any (default) or all"),
+        ("origin" = Option<String>, Query, description = "Datasets from any of these comma separated origins, e.g. PROD"),
+        ("type" = Option<String>, Query, description = "Datasets of any of these comma separated subtypes, e.g. view"),
+        ("-platform" = Option<String>, Query, description = "Datasets on none of these platforms"),
+        ("-tags" = Option<String>, Query, description = "Datasets without these tags"),
+        ("-origin" = Option<String>, Query, description = "Datasets from none of these origins"),
+        ("-type" = Option<String>, Query, description = "Datasets of none of these subtypes"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of datasets, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of datasets to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags, origin, subtype (not with name)"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order (default relevance); name matches can't sort by lastModified"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+        ("format" = Option<String>, Query, description = "json (default), csv or parquet, for this page; overrides the Accept header"),
+        ("report" = Option<String>, Query, description = "With csv or parquet, a row per dataset (datasets, the default) or per schema field (fields)"),
+    ),
+    responses(
+        (status = 200, description = "A page of datasets, as JSON or a report", content(
+            (Datasets = "application/json"),
+            (String = "text/csv"),
+            (Vec<u8> = "application/vnd.apache.parquet"),
+        ), headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown, conflicting or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The Accept header names no format the page can be sent as", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_query(
-    Extension(client): Extension<Client>,
-    req: Request<Body>
-) -> Json<schemas::Datasets>
+    Filtered(params): Filtered,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Response), ApiError>
+{
+    let list = ListFactory::new("DATASET", &query_values(&params.projection));
+    let (status, bytes) = listing::fetch(Route::Datasets, &list, &params, &backend, &cache, &ctx).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let (links, datasets) = listing::page::<Datasets>(&uri, &params, &body);
+    let datasets = datasets.project(params.projection);
+
+    Ok((status, links, report::render(&params.output, datasets, params.projection)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/datasets/search",
+    tag = "datasets",
+    summary = "Datasets matching a search query",
+    description = "`q` combines free text with `platform:`, `tag:`, `origin:`, `type:`, `name:` and \
+        `field:` terms using AND (or just a space), OR, NOT (or a leading `-`) and parentheses, e.g. \
+        `platform:snowflake AND tag:pii AND NOT name:tmp_* customer`. Values may be double quoted; \
+        `name:` and `field:` values may start or end with `*`. Free text is matched by DataHub's search \
+        and can only be ANDed with the rest of the query.",
+    params(
+        ("q" = String, Query, description = "The search query"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of datasets, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of datasets to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags, origin, subtype"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order (default relevance)"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "A page of datasets", body = Datasets, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "A malformed query, with the position of the mistake, or bad paging", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn search(
+    params: SearchParams,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<schemas::Datasets>), ApiError>
 {
-    let params = QueryParams::from(&req);
-    let body = match params.query {
-        QueryType::All          => GET_ALL.body(&params),
-        QueryType::Name(name)   => GET_BY_NAME.body(name, &params),
-        QueryType::Tags(tags)   => GET_BY_TAGS.body(tags, &params),
-        QueryType::Query(query) => GET_BY_QUERY.body(query, &params),
-    };
-    let resp = datahub::post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: QueryResponse = serde_json::from_slice(&bytes).unwrap();
-
-    Datasets::from(&body).into()
+    let factory = SearchFactory::new("DATASET", &query_values(&params.projection));
+    let body = factory.body(&params.search, &params.page);
+    let (status, bytes) = cache.fetch(Route::Datasets, &ctx, &body, || cursor::search(&backend, &ctx, &params.page, &body)).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+
+    let links = cursor::links(&uri, &params.page, &body);
+    let datasets = Datasets::from(&body)
+        .aggregate(&body, &params.facets)
+        .project(params.projection)
+        .link(&links);
+
+    Ok((status, links, datasets.into()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/datasets/search/explain",
+    tag = "datasets",
+    summary = "The GraphQL variables a search query compiles to",
+    params(
+        ("q" = String, Query, description = "The search query, as for /datasets/search"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of datasets, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of datasets to skip (default 0)"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("facets" = Option<String>, Query, description = "Accepted for parity with /datasets/search"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order"),
+        ("fields" = Option<String>, Query, description = "Accepted for parity with /datasets/search"),
+        ("include" = Option<String>, Query, description = "Accepted for parity with /datasets/search"),
+    ),
+    responses(
+        (status = 200, description = "The variables sent to DataHub's search", body = Object),
+        (status = 400, description = "A malformed query, with the position of the mistake, or bad paging", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn explain(params: SearchParams) -> Result<Json<serde_json::Value>, ApiError>
+{
+    let factory = SearchFactory::new("DATASET", &query_values(&params.projection));
+    let body = factory.body(&params.search, &params.page);
+
+    Ok(Json(serde_json::to_value(body.variables())?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/datasets/export",
+    tag = "datasets",
+    summary = "Every matching dataset as newline delimited JSON",
+    description = "Streams one dataset per line, paging through DataHub as the client reads, so the \
+        client may stop at any point. A failure part way through cuts the stream short rather than \
+        ending it cleanly.",
+    params(
+        ("query" = Option<String>, Query, description = "Datasets with any value like this"),
+        ("platform" = Option<String>, Query, description = "Datasets on any of these comma separated platforms"),
+        ("tags" = Option<String>, Query, description = "Datasets with any (or with tags_mode=all, every one) of these comma separated tags"),
+        ("tags_mode" = Option<String>, Query, description = "How tags combine: any (default) or all"),
+        ("origin" = Option<String>, Query, description = "Datasets from any of these comma separated origins, e.g. PROD"),
+        ("type" = Option<String>, Query, description = "Datasets of any of these comma separated subtypes, e.g. view"),
+        ("-platform" = Option<String>, Query, description = "Datasets on none of these platforms"),
+        ("-tags" = Option<String>, Query, description = "Datasets without these tags"),
+        ("-origin" = Option<String>, Query, description = "Datasets from none of these origins"),
+        ("-type" = Option<String>, Query, description = "Datasets of none of these subtypes"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order (default relevance)"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "One dataset per line", body = schemas::datasets::Dataset, content_type = "application/x-ndjson"),
+        (status = 400, description = "Unknown or conflicting query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn export(
+    params: ExportParams,
+    Extension(backend): Extension<Backend>,
+    ctx: Context
+) -> Result<impl IntoResponse, ApiError>
+{
+    let export = Export { values: query_values(&params.projection), params, backend, ctx };
+
+    // The first batch is fetched up front so a failure there is still an
+    // error response; after that the status has gone out with the headers.
+    let mut batch = export.batch(Cursor::start()).await?;
+    let (mut sender, stream) = Body::channel();
+    tokio::spawn(async move {
+        loop {
+            if sender.send_data(batch.lines).await.is_err() {
+                break
+            }
+            let cursor = match batch.next {
+                Some(cursor) => cursor,
+                None => break
+            };
+            match export.batch(cursor).await {
+                Ok(next) => batch = next,
+                Err(err) => {
+                    tracing::warn!(error = %err, "export cut short");
+                    sender.abort();
+                    break
+                }
+            }
+        }
+    }.instrument(tracing::Span::current()));
+
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body::boxed(stream)))
+}
+
+struct Export {
+    params: ExportParams,
+    values: String,
+    backend: Backend,
+    ctx: Context,
+}
+
+// A batch of exported datasets, one JSON document per line, and where the
+// next batch starts if there is one.
+struct Batch {
+    lines: Bytes,
+    next: Option<Cursor>,
+}
+
+impl Export {
+    async fn batch(&self, cursor: Cursor) -> Result<Batch, ApiError>
+    {
+        let params = &self.params;
+        let page = Page { limit: EXPORT_BATCH, start: cursor.offset, sort: params.sort, cursor: Some(cursor) };
+        let (get_all, by_query);
+        let body = match &params.query {
+            None => {
+                get_all = GetAllFactory::new("DATASET", &self.values);
+                get_all.body(&page, &params.filters)
+            },
+            Some(query) => {
+                by_query = QueryFactory::new("DATASET", &self.values);
+                by_query.body(query, &page, &params.filters)
+            },
+        };
+        let bytes = self.backend.search(&self.ctx, &body).await?;
+        let resp: QueryResponse = serde_json::from_slice(&bytes)?;
+
+        let (datasets, _) = resp.process::<DatasetEnvelope>();
+        let mut lines = Vec::new();
+        for dataset in datasets.into_iter().filter_map(|ds| ds.project(params.projection).into_dataset()) {
+            serde_json::to_writer(&mut lines, &dataset)?;
+            lines.push(b'\n');
+        }
+
+        let next = match (resp.extent(), resp.scroll_id()) {
+            (Some((count, _)), Some(scroll_id)) if count > 0 => Some(Cursor {
+                offset: page.start + count,
+                scroll_id: Some(scroll_id.to_owned())
+            }),
+            _ => None
+        };
+
+        Ok(Batch { lines: lines.into(), next })
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/datasets/{id}/tags",
+    tag = "datasets",
+    summary = "Add a tag to the specified dataset",
+    params(("id" = String, Path, description = "Dataset urn")),
+    request_body = requests::AddTag,
+    responses(
+        (status = 204, description = "The tag was added"),
+        (status = 400, description = "The body isn't a valid request", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't add the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn add_tag(
     Path(id): Path<String>,
     Json(payload): Json<requests::AddTag>,
-    Extension(client): Extension<Client>
-) -> StatusCode
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
 {
-    let body = ADD_TAG.body(&id, &payload.tag);
-    let resp = datahub::post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let status = resp.status();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let result: DatasetAddTagResponse = serde_json::from_slice(&bytes).unwrap();
-
-    if let Some(errors) = result.errors {
-        errors.iter().for_each(|msg| println!("{:?}", msg.message))
-    }
-    if status == StatusCode::OK {
-        match result.data {
-            Some(res) => if res.success { 
-                    StatusCode::NO_CONTENT
-                } else {
-                    StatusCode::UNPROCESSABLE_ENTITY
-                },
-            None => StatusCode::UNPROCESSABLE_ENTITY
-        }
-    } else {
-        status
-    }
+    tagging::add(&id, &payload.tag, TAGGING_ROUTES, &backend, &cache, &ctx).await
 }
 
+#[utoipa::path(
+    delete,
+    path = "/datasets/{id}/tags/{tag_id}",
+    tag = "datasets",
+    summary = "Remove a tag from the specified dataset",
+    params(
+        ("id" = String, Path, description = "Dataset urn"),
+        ("tag_id" = String, Path, description = "Tag urn"),
+    ),
+    responses(
+        (status = 204, description = "The tag was removed"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "DataHub didn't remove the tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn remove_tag(
     Path((id, tag_id)): Path<(String, String)>,
-    Extension(client): Extension<Client>
-) -> StatusCode
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
 {
-    let body = REMOVE_TAG.body(&id, &tag_id);
-    let resp = datahub::post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-
-    if resp.status() == StatusCode::OK {
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::INTERNAL_SERVER_ERROR
-    }
+    tagging::remove(&id, &tag_id, TAGGING_ROUTES, &backend, &cache, &ctx).await
 }
@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{
+        FromRequest,
+        RequestParts,
+        rejection::{JsonRejection, PathRejection},
+    },
+    response::{IntoResponse, Response},
+};
+
+use crate::error::ApiError;
+
+
+/// axum's `Json`, whose rejections are problem documents like the rest of
+/// the API's errors rather than plain text.
+pub struct Json<T>(pub T);
+
+/// axum's `Path`, rejecting path segments that don't decode as a problem
+/// document.
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for Json<T>
+where
+    axum::Json<T>: FromRequest<B, Rejection = JsonRejection>,
+    B: Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let axum::Json(value) = axum::Json::from_request(req).await?;
+        Ok(Json(value))
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    axum::Json<T>: IntoResponse,
+{
+    fn into_response(self) -> Response
+    {
+        axum::Json(self.0).into_response()
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self
+    {
+        Json(value)
+    }
+}
+
+#[async_trait]
+impl<T, B> FromRequest<B> for Path<T>
+where
+    axum::extract::Path<T>: FromRequest<B, Rejection = PathRejection>,
+    B: Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let axum::extract::Path(value) = axum::extract::Path::from_request(req).await?;
+        Ok(Path(value))
+    }
+}
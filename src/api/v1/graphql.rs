@@ -5,11 +5,12 @@ use crate::schemas::{
     Variables,
     Filter,
     SearchInput,
+    ScrollInput,
     AutoCompleteInput,
+    RelatedInput,
     TagAssociationInput,
-    ListRecommendationsInput
 };
-use crate::api::v1::{queries, params::QueryParams};
+use crate::api::v1::{queries, params::{Page, QueryParams, QueryType}, search::Search};
 
 
 pub struct GetOneFactory<'a> {
@@ -29,27 +30,42 @@ impl<'a> GetOneFactory<'a> {
     }
 }
 
+pub struct RelatedFactory<'a> {
+    query: String,
+    marker: PhantomData<&'a str>
+}
+
+impl<'a> RelatedFactory<'a> {
+    pub fn new(class: &str, values: &str) -> RelatedFactory<'a>
+    {
+        RelatedFactory { query: queries::related(class, values), marker: PhantomData }
+    }
+
+    pub fn body(&'a self, id: &'a str, page: &Page) -> GraphQL<'a>
+    {
+        GraphQL::new(&self.query, Variables::Related(RelatedInput { urn: id, start: page.start, count: page.limit }))
+    }
+}
 
 pub struct GetAllFactory<'a> {
     class: &'a str,
     query: String,
+    scroll: String,
 }
 
 impl<'a> GetAllFactory<'a> {
     pub fn new(class: &'a str, values: &str) -> GetAllFactory<'a>
     {
         GetAllFactory {
-            class, query: queries::by_query(values)
+            class, query: queries::by_query(values), scroll: queries::by_scroll(values)
         }
     }
 
-    pub fn body(&self, params: &QueryParams) -> GraphQL
+    pub fn body(&self, page: &Page, filters: &[Filter<'a>]) -> GraphQL<'_>
     {
-        GraphQL::new(
-            &self.query,
-            Variables::SearchInput(
-                SearchInput::new(self.class, "*".into(), params.start, params.limit, None)
-            )
+        paged(
+            &self.query, &self.scroll, page,
+            SearchInput::new(self.class, "*".into(), page.start, page.limit, filters).sorted(page.sort)
         )
     }
 }
@@ -65,72 +81,97 @@ impl<'a> NameFactory<'a> {
         NameFactory { class, query: queries::by_name(values) }
     }
 
-    pub fn body(&self, query: &'a str, params: &QueryParams) -> GraphQL
+    pub fn body(&self, query: &'a str, page: &Page) -> GraphQL<'_>
     {
         GraphQL::new(
             &self.query,
             Variables::AutoCompleteInput(
-                AutoCompleteInput::new(self.class, query, params.limit)
+                AutoCompleteInput::new(self.class, query, page.limit)
             )
         )
     }
 }
 
-pub struct TagsFactory<'a> {
+pub struct QueryFactory<'a> {
     class: &'a str,
     query: String,
+    scroll: String,
 }
 
-impl<'a> TagsFactory<'a> {
-    pub fn new(class: &'a str, values: &str) -> TagsFactory<'a>
+impl<'a> QueryFactory<'a> {
+    pub fn new(class: &'a str, values: &str) -> QueryFactory<'a>
     {
-        TagsFactory {
-            class,
-            query: queries::by_query(values),
+        QueryFactory {
+            class, query: queries::by_query(values), scroll: queries::by_scroll(values)
         }
     }
 
-    pub fn body(&'a self, query: &str, params: &QueryParams) -> GraphQL<'a>
+    pub fn body(&self, query: &str, page: &Page, filters: &[Filter<'a>]) -> GraphQL<'_>
     {
-        let tags = format!("tags:{query}");
+        let q = format!("*{query}*");
 
-        GraphQL::new(
-            &self.query,
-            Variables::SearchInput(
-                SearchInput::new(self.class, tags, params.start, params.limit, None)
-            )
+        paged(
+            &self.query, &self.scroll, page,
+            SearchInput::new(self.class, q, page.start, page.limit, filters).sorted(page.sort)
         )
     }
 }
 
-pub struct QueryFactory<'a> {
-    class: &'a str,
-    query: String,
+/// The searches behind a listing route: everything of a class, or the
+/// entities matching a `name` or `query`.
+pub struct ListFactory<'a> {
+    all: GetAllFactory<'a>,
+    by_name: NameFactory<'a>,
+    by_query: QueryFactory<'a>,
 }
 
-impl<'a> QueryFactory<'a> {
-    pub fn new(class: &'a str, values: &str) -> QueryFactory<'a>
+impl<'a> ListFactory<'a> {
+    pub fn new(class: &'a str, values: &str) -> ListFactory<'a>
     {
-        QueryFactory {
-            class, query: queries::by_query(values)
+        ListFactory {
+            all: GetAllFactory::new(class, values),
+            by_name: NameFactory::new(class, values),
+            by_query: QueryFactory::new(class, values),
         }
     }
 
-    pub fn body(&self, query: &'a str, params: &QueryParams) -> GraphQL
+    pub fn body(&'a self, params: &'a QueryParams) -> GraphQL<'a>
     {
-        let q = format!("*{query}*");
+        let page = &params.page;
+        match &params.query {
+            QueryType::All          => self.all.body(page, &params.filters),
+            QueryType::Name(name)   => self.by_name.body(name, page),
+            QueryType::Query(query) => self.by_query.body(query, page, &params.filters),
+        }
+    }
+}
 
-        GraphQL::new(
-            &self.query,
-            Variables::SearchInput(
-                SearchInput::new(self.class, q, params.start, params.limit, None)
-            )
+pub struct SearchFactory<'a> {
+    class: &'a str,
+    query: String,
+    scroll: String,
+}
+
+impl<'a> SearchFactory<'a> {
+    pub fn new(class: &'a str, values: &str) -> SearchFactory<'a>
+    {
+        SearchFactory { class, query: queries::by_query(values), scroll: queries::by_scroll(values) }
+    }
+
+    pub fn body(&self, search: &Search, page: &Page) -> GraphQL<'_>
+    {
+        paged(
+            &self.query, &self.scroll, page,
+            SearchInput::any_of(
+                self.class, search.text.clone(), page.start, page.limit, &search.filters
+            ).sorted(page.sort)
         )
     }
 }
 
 pub struct FilterFactory<'a> {
     query: String,
+    scroll: String,
     class: &'a str,
     filter: &'a str,
 }
@@ -139,43 +180,67 @@ impl<'a> FilterFactory<'a> {
     pub fn new(class: &'a str, values: &str, filter: &'a str) -> FilterFactory<'a>
     {
         FilterFactory {
-            class, filter, query: queries::by_query(values)
+            class, filter, query: queries::by_query(values), scroll: queries::by_scroll(values)
         }
     }
 
-    pub fn body(&self, value: &'a str, params: &QueryParams) -> GraphQL
+    pub fn body(&self, value: &'a str, page: &Page) -> GraphQL<'_>
     {
-        GraphQL::new(
-            &self.query,
-            Variables::SearchInput(
-                SearchInput::new(
-                    self.class, "*".into(), params.start, params.limit,
-                    Some(Filter::new(self.filter, value))
-                )
-            )
+        paged(
+            &self.query, &self.scroll, page,
+            SearchInput::new(
+                self.class, "*".into(), page.start, page.limit,
+                &[Filter::new(self.filter, value)]
+            ).sorted(page.sort)
         )
     }
 }
 
-pub struct PlatformsFactory<'a> {
-    user: String,
+// Cursor pages come from DataHub's scroll API and the rest from search.
+fn paged<'a>(query: &'a str, scroll: &'a str, page: &Page, input: SearchInput<'a>) -> GraphQL<'a>
+{
+    match &page.cursor {
+        Some(cursor) => {
+            let input = ScrollInput::new(input, cursor.scroll_id.clone());
+            GraphQL::new(scroll, Variables::ScrollInput(input))
+        },
+        None => GraphQL::new(query, Variables::SearchInput(input)),
+    }
+}
+
+/// A scroll through a search's results up to the end of its page, whose
+/// scroll id carries on where the page leaves off.
+pub fn seek<'a>(query: &'a str, search: &GraphQL<'a>) -> Option<GraphQL<'a>>
+{
+    match search.variables() {
+        Variables::SearchInput(input) => {
+            let end = input.start + input.count;
+            let input = ScrollInput { count: end, ..ScrollInput::new(input.clone(), None) };
+            Some(GraphQL::new(query, Variables::ScrollInput(input)))
+        },
+        _ => None
+    }
+}
+
+pub struct CountFactory<'a> {
+    class: &'a str,
+    filter: &'a str,
     query: String,
-    marker: PhantomData<&'a str>
 }
 
-impl<'a> PlatformsFactory<'a> {
-    pub fn new(values: &str) -> PlatformsFactory<'a>
+impl<'a> CountFactory<'a> {
+    pub fn new(class: &'a str, filter: &'a str) -> CountFactory<'a>
     {
-        let user = "urn:li:corpuser:datahub".into();
-        PlatformsFactory { user, query: queries::platforms(values), marker: PhantomData }
+        CountFactory { class, filter, query: queries::count() }
     }
 
-    pub fn body(&'a self, params: &QueryParams) -> GraphQL<'a>
+    /// The number of entities of the class with the value, without the entities.
+    pub fn body(&self, value: &'a str) -> GraphQL<'_>
     {
         GraphQL::new(
             &self.query,
-            Variables::ListRecommendationsInput(
-                ListRecommendationsInput::new(&self.user, params.limit)
+            Variables::SearchInput(
+                SearchInput::new(self.class, "*".into(), 0, 0, &[Filter::new(self.filter, value)])
             )
         )
     }
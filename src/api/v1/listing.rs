@@ -0,0 +1,47 @@
+use axum::http::Uri;
+use hyper::body::Bytes;
+
+use crate::api::v1::{
+    cursor,
+    graphql::ListFactory,
+    params::{QueryParams, QueryType},
+};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::schemas::{Collection, Links, QueryResponse};
+
+
+/// Fetches a page of a listing through the cache: name matches from
+/// DataHub's autocomplete, everything else from search.
+pub async fn fetch(
+    route: Route,
+    factory: &ListFactory<'_>,
+    params: &QueryParams,
+    backend: &Backend,
+    cache: &Cache,
+    ctx: &Context
+) -> Result<(CacheStatus, Bytes), ApiError>
+{
+    let body = factory.body(params);
+    cache.fetch(route, ctx, &body, || async { match &params.query {
+        QueryType::Name(..) => backend.autocomplete(ctx, &body).await,
+        _ => cursor::search(backend, ctx, &params.page, &body).await,
+    }}).await
+}
+
+/// The listing in DataHub's response, linked to the pages either side.
+/// Autocomplete can't sort, so name matches are ordered here instead.
+pub fn page<T>(uri: &Uri, params: &QueryParams, resp: &QueryResponse) -> (Links, T)
+    where T: Collection + for<'a> From<&'a QueryResponse<'a>>
+{
+    let listing = match params.query {
+        QueryType::Name(..) => T::from(resp).sort(params.page.sort),
+        _ => T::from(resp).aggregate(resp, &params.facets),
+    };
+    let links = cursor::links(uri, &params.page, resp);
+    let listing = listing.link(&links);
+
+    (links, listing)
+}
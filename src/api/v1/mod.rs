@@ -1,7 +1,549 @@
+mod cursor;
+mod extract;
 mod graphql;
+mod listing;
+mod openapi;
 mod params;
 mod queries;
+mod report;
+mod search;
+mod tagging;
 
 pub mod tags;
 pub mod datasets;
 pub mod platforms;
+pub mod charts;
+pub mod dashboards;
+pub mod pipelines;
+
+use axum::Router;
+
+use self::openapi::ApiRouter;
+
+
+pub fn routes() -> Router
+{
+    let api = ApiRouter::new();
+    let api = tags::routes(api);
+    let api = datasets::routes(api);
+    let api = platforms::routes(api);
+    let api = charts::routes(api);
+    let api = dashboards::routes(api);
+    let api = pipelines::routes(api);
+
+    api.into_router()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, path::Path, sync::{Arc, Mutex}, time::Duration};
+
+    use async_trait::async_trait;
+    use axum::{
+        Router,
+        body::Body,
+        extract::Extension,
+        http::{Request, StatusCode},
+    };
+    use hyper::body::Bytes;
+    use hyper::body::HttpBody;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use crate::backend::{Backend, MemoryBackend, MetadataBackend};
+    use crate::cache::Cache;
+    use crate::config::CacheConfig;
+    use crate::context::Context;
+    use crate::error::ApiError;
+    use crate::health::Check;
+    use crate::schemas::GraphQL;
+
+
+    const METHODS: [&str; 5] = ["GET", "POST", "PUT", "DELETE", "PATCH"];
+
+    // Serves the fixture like the memory backend, noting each GraphQL
+    // query the routes send it, and fails searches once it has seen
+    // `fails_after` queries.
+    struct Recorder {
+        memory: MemoryBackend,
+        queries: Mutex<Vec<String>>,
+        fails_after: usize,
+    }
+
+    impl Recorder {
+        fn record(&self, body: &GraphQL<'_>)
+        {
+            let body = serde_json::to_value(body).expect("serializable body");
+            let query = body["query"].as_str().expect("query").to_owned();
+            self.queries.lock().expect("unpoisoned").push(query);
+        }
+
+        fn last(&self) -> String
+        {
+            self.queries.lock().expect("unpoisoned").last().cloned().expect("a query")
+        }
+
+        fn count(&self) -> usize
+        {
+            self.queries.lock().expect("unpoisoned").len()
+        }
+    }
+
+    #[async_trait]
+    impl MetadataBackend for Recorder {
+        async fn search(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+        {
+            self.record(body);
+            if self.count() > self.fails_after {
+                return Err(ApiError::Upstream(StatusCode::INTERNAL_SERVER_ERROR))
+            }
+            self.memory.search(ctx, body).await
+        }
+
+        async fn autocomplete(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+        {
+            self.record(body);
+            self.memory.autocomplete(ctx, body).await
+        }
+
+        async fn get(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+        {
+            self.record(body);
+            self.memory.get(ctx, body).await
+        }
+
+        async fn add_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+        {
+            self.record(body);
+            self.memory.add_tag(ctx, body).await
+        }
+
+        async fn remove_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+        {
+            self.record(body);
+            self.memory.remove_tag(ctx, body).await
+        }
+
+        async fn ingest(&self, ctx: &Context, body: String) -> Result<Bytes, ApiError>
+        {
+            self.memory.ingest(ctx, body).await
+        }
+
+        async fn probe(&self, timeout: Duration) -> Vec<Check>
+        {
+            self.memory.probe(timeout).await
+        }
+    }
+
+    fn memory() -> MemoryBackend
+    {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/catalog.json");
+        MemoryBackend::from_file(&fixture).expect("fixture loads")
+    }
+
+    fn recorded() -> (Router, Arc<Recorder>)
+    {
+        record(memory(), usize::MAX)
+    }
+
+    fn record(memory: MemoryBackend, fails_after: usize) -> (Router, Arc<Recorder>)
+    {
+        let recorder = Arc::new(Recorder { memory, queries: Mutex::default(), fails_after });
+        (serve(recorder.clone()), recorder)
+    }
+
+    // `n` bare datasets, named in order.
+    fn datasets(n: usize) -> MemoryBackend
+    {
+        let datasets = (0..n)
+            .map(|i| json!({
+                "__typename": "Dataset",
+                "urn": format!("urn:li:dataset:(urn:li:dataPlatform:hive,d{i:04},PROD)"),
+                "name": format!("d{i:04}"),
+                "properties": { "name": format!("d{i:04}"), "origin": "PROD" },
+            }))
+            .collect();
+        MemoryBackend::new(datasets)
+    }
+
+    // The status of an export and the lines it streamed, up to the end of
+    // the body or the error cutting it short.
+    async fn exported(app: &Router, uri: &str) -> (StatusCode, Vec<Value>, Option<axum::Error>)
+    {
+        let req = Request::builder().uri(uri).body(Body::empty()).expect("valid request");
+        let res = app.clone().oneshot(req).await.expect("infallible router");
+        let status = res.status();
+        let mut body = res.into_body();
+        let mut bytes = Vec::new();
+        let mut error = None;
+        let read = async {
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(chunk) => bytes.extend_from_slice(&chunk),
+                    Err(err) => {
+                        error = Some(err);
+                        break
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), read).await.expect("the stream ends");
+
+        let lines = bytes.split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).expect("a JSON line"))
+            .collect();
+        (status, lines, error)
+    }
+
+    fn serve(backend: Backend) -> Router
+    {
+        let cache = Arc::new(Cache::new(&CacheConfig::default()).expect("default cache config"));
+
+        super::routes()
+            .layer(Extension(backend))
+            .layer(Extension(cache))
+    }
+
+    fn app() -> Router
+    {
+        serve(Arc::new(memory()))
+    }
+
+    async fn call(app: &Router, method: &str, uri: &str) -> (StatusCode, Option<Value>)
+    {
+        let req = Request::builder().method(method).uri(uri).body(Body::empty()).expect("valid request");
+        let res = app.clone().oneshot(req).await.expect("infallible router");
+        let status = res.status();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.expect("readable body");
+
+        (status, serde_json::from_slice(&bytes).ok())
+    }
+
+    // The response status, its X-Cache header and its JSON body.
+    async fn cached(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, String, Option<Value>)
+    {
+        let req = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+        let req = req.body(body.map_or_else(Body::empty, |b| Body::from(b.to_string()))).expect("valid request");
+        let res = app.clone().oneshot(req).await.expect("infallible router");
+        let status = res.status();
+        let cache = res.headers().get("X-Cache").and_then(|v| v.to_str().ok()).unwrap_or_default().to_owned();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.expect("readable body");
+
+        (status, cache, serde_json::from_slice(&bytes).ok())
+    }
+
+    // The status, Content-Type and Vary headers and body of a GET sent
+    // with `accept`.
+    async fn negotiated(app: &Router, uri: &str, accept: &str) -> (StatusCode, String, String, Bytes)
+    {
+        let req = Request::builder().uri(uri).header("Accept", accept).body(Body::empty()).expect("valid request");
+        let res = app.clone().oneshot(req).await.expect("infallible router");
+        let header = |name| res.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_owned();
+        let (status, content_type, vary) = (res.status(), header("Content-Type"), header("Vary"));
+        let bytes = hyper::body::to_bytes(res.into_body()).await.expect("readable body");
+
+        (status, content_type, vary, bytes)
+    }
+
+    // The spec's operations, with `{param}` path segments filled in.
+    async fn operations(app: &Router) -> Vec<(String, Value)>
+    {
+        let (status, spec) = call(app, "GET", "/openapi.json").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let spec = spec.expect("JSON OpenAPI document");
+        spec["paths"].as_object().expect("paths").iter()
+            .map(|(path, item)| {
+                let uri = path.split('/')
+                    .map(|segment| if segment.starts_with('{') { "urn:li:x:y" } else { segment })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (uri, item.clone())
+            })
+            .collect()
+    }
+
+    // The router answers paths and methods it doesn't serve with an empty
+    // 404 or 405, where handlers answer with a problem document.
+    fn routed(status: StatusCode, body: &Option<Value>) -> bool
+    {
+        body.is_some() || !matches!(status, StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED)
+    }
+
+    #[tokio::test]
+    async fn routes_match_the_spec()
+    {
+        let app = app();
+        for (uri, item) in operations(&app).await {
+            for method in METHODS {
+                let (status, body) = call(&app, method, &uri).await;
+                let declared = item.get(method.to_lowercase()).is_some();
+                assert_eq!(routed(status, &body), declared, "{method} {uri} is routed but not in the spec or vice versa");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn query_params_match_the_spec()
+    {
+        let app = app();
+        for (uri, item) in operations(&app).await {
+            for method in METHODS.iter().filter(|m| item.get(m.to_lowercase()).is_some()) {
+                let declared: BTreeSet<_> = item[method.to_lowercase()]["parameters"].as_array().into_iter()
+                    .flatten()
+                    .filter(|p| p["in"] == "query")
+                    .filter_map(|p| p["name"].as_str())
+                    .map(str::to_owned)
+                    .collect();
+
+                // Extractors reject an unknown parameter by listing the ones
+                // they accept; routes without one ignore the query string.
+                let (_, body) = call(&app, method, &format!("{uri}?not_a_param=1")).await;
+                let accepted: BTreeSet<_> = body.as_ref()
+                    .and_then(|b| b["invalid-params"].as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter(|p| p["name"] == "not_a_param")
+                    .filter_map(|p| p["reason"].as_str()?.split_once("expected one of "))
+                    .flat_map(|(_, keys)| keys.split(", ").map(str::to_owned))
+                    .collect();
+
+                assert_eq!(accepted, declared, "{method} {uri} accepts other query parameters than the spec declares");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn free_text_may_be_escaped()
+    {
+        let app = app();
+        let (status, body) = call(&app, "GET", "/tags/urn:li:tag:finance").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("tag")["tag"]["description"], "Owned by the \"finance\" team");
+
+        let (status, body) = call(&app, "GET", "/datasets/urn:li:dataset:(urn:li:dataPlatform:hive,sales.customers,PROD)").await;
+        assert_eq!(status, StatusCode::OK);
+        let dataset = &body.expect("dataset")["dataset"];
+        assert_eq!(dataset["fields"][1]["nativeType"], "varchar(320) COMMENT \"masked\"");
+
+        let (status, body) = call(&app, "GET", "/datasets/urn:li:dataset:(urn:li:dataPlatform:hive,sales.orders,PROD)").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("dataset")["dataset"]["tags"][0]["tag"]["description"], "Owned by the \"finance\" team");
+
+        let (status, body) = call(&app, "GET", "/charts/urn:li:chart:(looker,sales.top_customers)").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("chart")["chart"]["description"], "Customers ranked by \"lifetime value\"");
+
+        let (status, body) = call(&app, "GET", "/dashboards/urn:li:dashboard:(looker,sales.overview)").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("dashboard")["dashboard"]["description"], "Revenue and customers at a glance\nSee the \"Sales\" space for more");
+
+        let (status, body) = call(&app, "GET", "/pipelines/urn:li:dataFlow:(alteryx,customer_scoring,prod)").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("pipeline")["pipeline"]["description"], "Scores customers for the \"sales\" team");
+
+        let (status, body) = call(&app, "GET", "/pipelines/urn:li:dataFlow:(airflow,sales_daily,prod)/jobs").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("jobs")["data"][0]["job"]["description"], "Copies the day's orders into the lake\nRuns at 01:00 UTC");
+    }
+
+    #[tokio::test]
+    async fn pipeline_jobs_are_paged()
+    {
+        let app = app();
+        let jobs = "/pipelines/urn:li:dataFlow:(airflow,sales_daily,prod)/jobs";
+
+        let (status, body) = call(&app, "GET", &format!("{jobs}?limit=1")).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.expect("jobs");
+        assert_eq!(body["data"].as_array().map(Vec::len), Some(1));
+        assert_eq!(body["paging"]["total"], 2);
+        assert_eq!(body["paging"]["next"], format!("{jobs}?limit=1&offset=1"));
+
+        let (_, body) = call(&app, "GET", &format!("{jobs}?limit=1&offset=1")).await;
+        let body = body.expect("jobs");
+        assert_eq!(body["data"][0]["job"]["name"], "rollup_revenue");
+        assert_eq!(body["paging"]["next"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn dataset_jobs_read_or_write_the_dataset()
+    {
+        let app = app();
+        let jobs = "/datasets/urn:li:dataset:(urn:li:dataPlatform:hive,sales.orders,PROD)/jobs?fields=name";
+
+        let (status, body) = call(&app, "GET", jobs).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.expect("jobs");
+        let names: Vec<_> = body["data"].as_array().expect("data").iter()
+            .map(|e| e["job"]["name"].clone())
+            .collect();
+        assert_eq!(names, ["load_orders", "rollup_revenue"]);
+        assert_eq!(body["data"][0]["job"]["outputs"][0]["dataset"]["name"], "orders");
+        assert_eq!(body["paging"]["total"], 2);
+
+        let (status, _) = call(&app, "GET", "/datasets/urn:li:dataset:nope/jobs").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn dataset_listings_follow_the_projection()
+    {
+        let (app, recorder) = recorded();
+        for listing in ["/tags/urn:li:tag:pii/datasets", "/platforms/urn:li:dataPlatform:hive/datasets"] {
+            let (status, body) = call(&app, "GET", listing).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(recorder.last().contains("schemaMetadata"), "{listing} fetches every aspect by default");
+            let dataset = &body.expect("datasets")["data"][0]["dataset"];
+            assert!(dataset.get("fields").is_some() && dataset.get("tags").is_some());
+
+            let (status, body) = call(&app, "GET", &format!("{listing}?fields=name&include=none")).await;
+            assert_eq!(status, StatusCode::OK);
+            let query = recorder.last();
+            for aspect in ["schemaMetadata", "subTypes", "tags {", "displayName"] {
+                assert!(!query.contains(aspect), "{listing} asks for {aspect} without wanting it");
+            }
+            let dataset = body.expect("datasets")["data"][0]["dataset"].clone();
+            let keys: BTreeSet<_> = dataset.as_object().expect("dataset").keys().cloned().collect();
+            assert_eq!(keys, BTreeSet::from(["id".to_owned(), "name".to_owned()]));
+
+            let (status, _) = call(&app, "GET", &format!("{listing}?fields=fields&include=tags")).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_are_cached_until_tagging_changes_them()
+    {
+        let app = app();
+        let dataset = "/datasets/urn:li:dataset:(urn:li:dataPlatform:hive,sales.customers,PROD)";
+        let tagged = "/tags/urn:li:tag:finance/datasets?fields=name";
+        let tags = |body: Option<Value>| -> Vec<Value> {
+            body.expect("dataset")["dataset"]["tags"].as_array().expect("tags").iter()
+                .map(|t| t["tag"]["id"].clone())
+                .collect()
+        };
+
+        let (status, cache, _) = cached(&app, "GET", dataset, None).await;
+        assert_eq!((status, cache.as_str()), (StatusCode::OK, "MISS"));
+        let (_, cache, body) = cached(&app, "GET", dataset, None).await;
+        assert_eq!(cache, "HIT");
+        assert_eq!(tags(body), ["urn:li:tag:pii"]);
+        cached(&app, "GET", tagged, None).await;
+        assert_eq!(cached(&app, "GET", tagged, None).await.1, "HIT");
+
+        let add = serde_json::json!({ "tag": "urn:li:tag:finance" });
+        let (status, ..) = cached(&app, "POST", &format!("{dataset}/tags"), Some(add)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, cache, body) = cached(&app, "GET", dataset, None).await;
+        assert_eq!(cache, "MISS");
+        assert_eq!(tags(body), ["urn:li:tag:pii", "urn:li:tag:finance"]);
+        let (_, cache, body) = cached(&app, "GET", tagged, None).await;
+        assert_eq!(cache, "MISS");
+        assert!(body.expect("datasets")["data"].as_array().expect("data").iter()
+            .any(|d| d["dataset"]["name"] == "customers"));
+
+        let (status, ..) = cached(&app, "DELETE", &format!("{dataset}/tags/urn:li:tag:finance"), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, cache, body) = cached(&app, "GET", dataset, None).await;
+        assert_eq!(cache, "MISS");
+        assert_eq!(tags(body), ["urn:li:tag:pii"]);
+        assert_eq!(cached(&app, "GET", tagged, None).await.1, "MISS");
+    }
+    #[tokio::test]
+    async fn listings_are_sent_as_the_caller_accepts()
+    {
+        let app = app();
+        let uris = ["/datasets", "/tags/urn:li:tag:pii/datasets", "/platforms/urn:li:dataPlatform:hive/datasets"];
+        for uri in uris {
+            let (status, content_type, vary, body) = negotiated(&app, uri, "text/html, */*;q=0.8").await;
+            assert_eq!((status, content_type.as_str(), vary.as_str()), (StatusCode::OK, "application/json", "accept"));
+            assert!(serde_json::from_slice::<Value>(&body).is_ok());
+
+            let (status, content_type, vary, body) = negotiated(&app, uri, "text/csv;q=0.9, application/json").await;
+            assert_eq!((status, content_type.as_str(), vary.as_str()), (StatusCode::OK, "text/csv", "accept"));
+            assert!(body.starts_with(b"id,"));
+
+            let (status, content_type, _, body) = negotiated(&app, uri, "application/vnd.apache.parquet").await;
+            assert_eq!((status, content_type.as_str()), (StatusCode::OK, "application/vnd.apache.parquet"));
+            assert!(body.starts_with(b"PAR1"));
+
+            let (status, content_type, _, body) = negotiated(&app, uri, "application/xml").await;
+            assert_eq!((status, content_type.as_str()), (StatusCode::NOT_ACCEPTABLE, "application/problem+json"));
+            let problem: Value = serde_json::from_slice(&body).expect("JSON problem");
+            assert_eq!(problem["status"], 406);
+
+            // `format` wins over the header, and bad parameters over both.
+            let (status, content_type, ..) = negotiated(&app, &format!("{uri}?format=csv"), "application/xml").await;
+            assert_eq!((status, content_type.as_str()), (StatusCode::OK, "text/csv"));
+            let (status, ..) = negotiated(&app, &format!("{uri}?limit=0"), "application/xml").await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+    #[tokio::test]
+    async fn exports_stream_every_batch()
+    {
+        let (app, recorder) = record(datasets(250), usize::MAX);
+        let (status, lines, error) = exported(&app, "/datasets/export?sort=name&fields=name").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(error.is_none());
+        assert_eq!(lines.len(), 250);
+        let names: Vec<_> = lines.iter().map(|line| line["name"].as_str().expect("name")).collect();
+        let expected: Vec<_> = (0..250).map(|i| format!("d{i:04}")).collect();
+        assert_eq!(names, expected);
+        assert_eq!(recorder.count(), 3);
+
+        let (_, lines, _) = exported(&app, "/datasets/export?query=d01").await;
+        assert_eq!(lines.len(), 100);
+        assert_eq!(recorder.count(), 4);
+    }
+
+    #[tokio::test]
+    async fn exports_failing_part_way_end_short()
+    {
+        let (app, recorder) = record(datasets(250), 2);
+        let (status, lines, error) = exported(&app, "/datasets/export").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(lines.len(), 200);
+        assert!(error.is_some());
+        assert_eq!(recorder.count(), 3);
+
+        // Until the first batch is in, a failure is still a problem document.
+        let (app, _) = record(datasets(250), 0);
+        let (status, body) = call(&app, "GET", "/datasets/export").await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(body.expect("problem")["status"], 502);
+    }
+    #[tokio::test]
+    async fn malformed_bodies_and_paths_are_problems()
+    {
+        let app = app();
+        let dataset = "/datasets/urn:li:dataset:(urn:li:dataPlatform:hive,sales.orders,PROD)/tags";
+        let requests = [
+            ("POST", "/tags", Some("application/json"), "{\"name\": "),
+            ("POST", "/tags", Some("application/json"), "{\"title\": \"finance\"}"),
+            ("POST", "/tags", None, "{\"name\": \"finance\"}"),
+            ("POST", dataset, Some("application/json"), "[]"),
+            ("GET", "/tags/urn:li:tag:%FF", None, ""),
+        ];
+        for (method, uri, content_type, body) in requests {
+            let req = Request::builder().method(method).uri(uri);
+            let req = match content_type {
+                Some(content_type) => req.header("Content-Type", content_type),
+                None => req,
+            };
+            let req = req.body(Body::from(body)).expect("valid request");
+            let res = app.clone().oneshot(req).await.expect("infallible router");
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{method} {uri} {body}");
+            assert_eq!(res.headers()["Content-Type"], "application/problem+json");
+
+            let bytes = hyper::body::to_bytes(res.into_body()).await.expect("readable body");
+            let problem: Value = serde_json::from_slice(&bytes).expect("JSON problem");
+            assert_eq!(problem["status"], 400);
+            assert!(problem["detail"].as_str().is_some_and(|detail| detail.starts_with("invalid ")));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    extract::Path as UriPath,
+    handler::Handler,
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, on, MethodFilter},
+};
+use hyper::{Body, body::Bytes};
+use utoipa::{
+    OpenApi,
+    Path,
+    openapi::{
+        InfoBuilder,
+        OpenApiBuilder,
+        SecurityRequirement,
+        ServerBuilder,
+        path::{HttpMethod, PathsBuilder},
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+use utoipa_swagger_ui::Config;
+
+use crate::error::Problem;
+use crate::schemas::{
+    requests,
+    Charts,
+    ChartEnvelope,
+    Dashboards,
+    DashboardEnvelope,
+    Datasets,
+    DatasetEnvelope,
+    Jobs,
+    Pipelines,
+    PipelineEnvelope,
+    Platforms,
+    PlatformEnvelope,
+    Tags,
+    TagEnvelope,
+};
+use crate::telemetry::traced;
+
+
+const PREFIX: &str = "/api/v1";
+
+/// The schemas the route descriptions refer to, each of which brings along
+/// the schemas it's made of.
+#[derive(OpenApi)]
+#[openapi(components(schemas(
+    Tags, TagEnvelope,
+    Datasets, DatasetEnvelope,
+    Platforms, PlatformEnvelope,
+    Charts, ChartEnvelope,
+    Dashboards, DashboardEnvelope,
+    Pipelines, PipelineEnvelope, Jobs,
+    requests::AddTag, requests::CreateTag,
+    Problem,
+)))]
+struct Schemas;
+
+/// A router that collects the `utoipa::path` description of every route
+/// it serves, so the OpenAPI document can't drift from the routes.
+pub struct ApiRouter {
+    router: Router,
+    paths: PathsBuilder,
+}
+
+impl ApiRouter {
+    pub fn new() -> ApiRouter
+    {
+        ApiRouter { router: Router::new(), paths: PathsBuilder::new() }
+    }
+
+    /// Serves the handler at the path and methods its description declares.
+    pub fn route<P, H, T>(mut self, handler: H) -> ApiRouter
+        where P: Path, H: Handler<T, Body>, T: 'static
+    {
+        let path = P::path().replace('{', ":").replace('}', "");
+        let filter = P::methods().into_iter()
+            .fold(MethodFilter::empty(), |filter, method| filter | method_filter(method));
+
+        self.router = self.router.route(&path, on(filter, handler).layer(traced(path.clone())));
+        self.paths = self.paths.path_from::<P>();
+
+        self
+    }
+
+    /// The routes plus the OpenAPI document and a Swagger UI page for them.
+    pub fn into_router(self) -> Router
+    {
+        let mut components = Schemas::openapi().components.unwrap_or_default();
+        components.add_security_scheme("bearer", SecurityScheme::Http(
+            HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()
+        ));
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(
+            ApiKey::Header(ApiKeyValue::new("X-Api-Key"))
+        ));
+
+        // Credentials are optional: they are forwarded to DataHub and
+        // identify the caller of a mutation.
+        let security = vec![
+            SecurityRequirement::default(),
+            SecurityRequirement::new("bearer", Vec::<String>::new()),
+            SecurityRequirement::new("api_key", Vec::<String>::new()),
+        ];
+
+        let doc = OpenApiBuilder::new()
+            .info(InfoBuilder::new()
+                .title("rs-datasvc")
+                .description(Some("Restful frontend to DataHub"))
+                .version(env!("CARGO_PKG_VERSION"))
+                .build()
+            )
+            .servers(Some([ServerBuilder::new().url(PREFIX).build()]))
+            .paths(self.paths.build())
+            .components(Some(components))
+            .security(Some(security))
+            .build();
+        let spec = Bytes::from(doc.to_json().expect("serializable OpenAPI document"));
+        let config = Arc::new(Config::new([format!("{PREFIX}/openapi.json")]));
+
+        self.router
+            .route("/openapi.json", get(move || async move {
+                ([(header::CONTENT_TYPE, "application/json")], spec)
+            }))
+            .route("/docs", get(|| async { Redirect::permanent("docs/") }))
+            .route("/docs/*file", get(move |UriPath(file): UriPath<String>| {
+                let config = config.clone();
+                async move { swagger_ui(&file, config) }
+            }))
+    }
+}
+
+/// Serves the Swagger UI assets built into the binary, so the page works
+/// without reaching a CDN.
+fn swagger_ui(file: &str, config: Arc<Config<'static>>) -> Response
+{
+    match utoipa_swagger_ui::serve(file.trim_start_matches('/'), config) {
+        Ok(Some(file)) => ([(header::CONTENT_TYPE, file.content_type)], file.bytes.into_owned()).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+    }
+}
+
+fn method_filter(method: HttpMethod) -> MethodFilter
+{
+    match method {
+        HttpMethod::Get     => MethodFilter::GET,
+        HttpMethod::Post    => MethodFilter::POST,
+        HttpMethod::Put     => MethodFilter::PUT,
+        HttpMethod::Delete  => MethodFilter::DELETE,
+        HttpMethod::Options => MethodFilter::OPTIONS,
+        HttpMethod::Head    => MethodFilter::HEAD,
+        HttpMethod::Patch   => MethodFilter::PATCH,
+        HttpMethod::Trace   => MethodFilter::TRACE,
+    }
+}
@@ -1,55 +1,774 @@
-use std::convert::From;
-use std::collections::HashMap;
+use std::{collections::HashMap, marker::PhantomData};
 
-use axum::http::Request;
-use hyper::Body;
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequest, RequestParts},
+    http::header,
+};
 
-pub enum QueryType<'a> {
+use crate::api::v1::cursor::Cursor;
+use crate::api::v1::report::{Format, Output, Rows};
+use crate::api::v1::search::{self, Search};
+use crate::error::{ApiError, InvalidParam};
+use crate::schemas::{Charts, Dashboards, Datasets, Facet, Filter, Pipelines, Projection, Sort, SortKey, Tags};
+
+
+const DEFAULT_LIMIT: i32 = 10;
+const MAX_LIMIT: i32 = 1000;
+
+// DataHub's search index won't page past this many results.
+pub(super) const MAX_WINDOW: i32 = 10_000;
+
+pub enum QueryType {
     All,
-    Name(&'a str),
-    Tags(&'a str),
-    Query(&'a str),
+    Name(String),
+    Query(String),
+}
+
+/// The query string of the search routes: at most one of `query` or
+/// `name`, plus paging, the facets to aggregate and any filters.
+pub struct QueryParams {
+    pub query: QueryType,
+    pub page: Page,
+    pub facets: Vec<Facet>,
+    pub filters: Vec<Filter<'static>>,
+    pub projection: Projection,
+    pub output: Output,
+}
+
+/// The query parameters of a listing of `T`, whose facets and filters are
+/// those of its `Scope`.
+pub struct Listed<T>(pub QueryParams, pub PhantomData<T>);
+
+/// What the entities of a listing can be counted by with `facets` and the
+/// filter parameters narrowing them down. Only datasets have an origin or
+/// subtype.
+pub trait Scope {
+    const FACETS: &'static [Facet];
+    const FILTERS: &'static [&'static str];
+}
+
+/// Dataset search route parameters that may also filter the results by
+/// `platform`, `tags`, `origin` and `type`, or exclude them with a leading
+/// `-`, and pick the dataset fields to return with `fields` and `include`,
+/// or ask for a CSV or Parquet report instead of JSON.
+pub struct Filtered(pub QueryParams);
+
+/// The query string of the search DSL routes: `q` plus paging, facets and
+/// the dataset fields to return.
+pub struct SearchParams {
+    pub search: Search,
+    pub page: Page,
+    pub facets: Vec<Facet>,
+    pub projection: Projection,
+}
+
+/// The query string of routes listing an entity's related entities, which
+/// DataHub pages by `offset` alone, with the dataset fields to return.
+pub struct RelatedParams {
+    pub page: Page,
+    pub projection: Projection,
 }
 
-pub struct QueryParams<'a> {
-    pub query: QueryType<'a>,
+/// The query string of the export route: the query, filters, sort and
+/// fields of `Filtered`, with no paging.
+pub struct ExportParams {
+    pub query: Option<String>,
+    pub filters: Vec<Filter<'static>>,
+    pub sort: Option<Sort>,
+    pub projection: Projection,
+}
+
+/// The `limit` and `offset` or `cursor` query parameters, and `sort` where
+/// a route takes one. Pages are searched from the offset, or scrolled from
+/// where the cursor left off.
+pub struct Page {
     pub limit: i32,
-    pub start: i32, 
+    pub start: i32,
+    pub sort: Option<Sort>,
+    pub cursor: Option<Cursor>,
+}
+
+/// Paging parameters for dataset listings, which may also be sorted by
+/// any `SortKey`, pick the dataset fields to return with `fields` and
+/// `include`, and be sent as a CSV or Parquet report.
+pub struct Listing {
+    pub page: Page,
+    pub projection: Projection,
+    pub output: Output,
+}
+
+// How a filter value is spelled in the DataHub field.
+type Spelling = fn(&str) -> String;
+
+// The filter parameters and the DataHub field each one filters on.
+pub(super) const FILTERS: &[(&str, &str, Spelling)] = &[
+    ("platform", "platform",  |v| urn("urn:li:dataPlatform:", v)),
+    ("tags",     "tags",      |v| urn("urn:li:tag:", v)),
+    ("origin",   "origin",    |v| v.to_uppercase()),
+    ("type",     "typeNames", capitalize),
+];
+
+impl Scope for Datasets {
+    const FACETS: &'static [Facet] = &Facet::ALL;
+    const FILTERS: &'static [&'static str] = &["platform", "tags", "origin", "type"];
+}
+
+impl Scope for Tags {
+    const FACETS: &'static [Facet] = &Facet::ALL;
+    const FILTERS: &'static [&'static str] = &[];
+}
+
+impl Scope for Charts {
+    const FACETS: &'static [Facet] = &[Facet::Platform, Facet::Tags];
+    const FILTERS: &'static [&'static str] = &["platform", "tags"];
+}
+
+impl Scope for Dashboards {
+    const FACETS: &'static [Facet] = &[Facet::Platform, Facet::Tags];
+    const FILTERS: &'static [&'static str] = &["platform", "tags"];
 }
 
-impl<'a> From<&'a Request<Body>> for QueryParams<'a> {
-    fn from(req: &'a Request<Body>) -> QueryParams<'a>
+impl Scope for Pipelines {
+    const FACETS: &'static [Facet] = &[Facet::Platform, Facet::Tags];
+    const FILTERS: &'static [&'static str] = &["platform", "tags"];
+}
+
+#[async_trait]
+impl<T: Scope, B: Send> FromRequest<B> for Listed<T> {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
     {
-        let params: HashMap<_, _> = req.uri().query()
-            .map_or_else(HashMap::new, parse_query);
+        let keys = with_filters(&["query", "name", "limit", "offset", "cursor", "facets", "sort"], T::FILTERS);
+        let mut params = Params::parse(req.uri().query(), &keys.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut query = params.query::<T>();
+        params.sort_query(&mut query, &[SortKey::Name]);
 
-        let limit = params.get("limit").map_or(10, |s| s.parse::<i32>().unwrap());
-        let start = params.get("offset").map_or(0, |s| s.parse::<i32>().unwrap());
+        params.finish()?;
+        Ok(Listed(query, PhantomData))
+    }
+}
 
-        let query = if let Some(query) = params.get("query") {
-            QueryType::Query(query)
-        } else if let Some(name) = params.get("name") {
-            QueryType::Name(name)
-        } else if let Some(tags) = params.get("tags") {
-            QueryType::Tags(tags)
-        } else {
-            QueryType::All
+#[async_trait]
+impl<B: Send> FromRequest<B> for Filtered {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let keys = with_filters(&[
+            "query", "name", "limit", "offset", "cursor", "facets", "sort", "fields", "include", "format", "report"
+        ], Datasets::FILTERS);
+        let mut params = Params::parse(req.uri().query(), &keys.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut query = params.query::<Datasets>();
+        params.sort_query(&mut query, &SortKey::ALL);
+        query.projection = params.projection();
+        let output = params.output(accept(req), query.projection);
+
+        params.finish()?;
+        query.output = output?;
+        Ok(Filtered(query))
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ExportParams {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let keys = with_filters(&["query", "sort", "fields", "include"], Datasets::FILTERS);
+        let mut params = Params::parse(req.uri().query(), &keys.iter().map(String::as_str).collect::<Vec<_>>());
+        let query = params.text("query");
+        let filters = params.filters(Datasets::FILTERS);
+        let sort = params.sort(&SortKey::ALL);
+        let projection = params.projection();
+
+        params.finish()?;
+        Ok(ExportParams { query, filters, sort, projection })
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for SearchParams {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let keys = ["q", "limit", "offset", "cursor", "facets", "sort", "fields", "include"];
+        let mut params = Params::parse(req.uri().query(), &keys);
+        let mut page = params.page();
+        page.sort = params.sort(&SortKey::ALL);
+        let facets = params.facets(Datasets::FACETS);
+        let projection = params.projection();
+        let search = match params.text("q").map(|q| search::parse(&q)) {
+            Some(Ok(search)) => Some(search),
+            Some(Err(err)) => {
+                params.invalid_at("q", err.reason, err.position);
+                None
+            },
+            None => {
+                params.invalid("q", "is required".into());
+                None
+            }
         };
 
-        QueryParams { query, start, limit }
+        params.finish()?;
+        Ok(SearchParams { search: search.unwrap_or_default(), page, facets, projection })
     }
 }
 
-fn parse_query(query: &str) -> HashMap<&str, &str>
+#[async_trait]
+impl<B: Send> FromRequest<B> for Projection {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let mut params = Params::parse(req.uri().query(), &["fields", "include"]);
+        let projection = params.projection();
+
+        params.finish()?;
+        Ok(projection)
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for RelatedParams {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let mut params = Params::parse(req.uri().query(), &["limit", "offset", "fields", "include"]);
+        let page = params.page();
+        let projection = params.projection();
+
+        params.finish()?;
+        Ok(RelatedParams { page, projection })
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Page {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let mut params = Params::parse(req.uri().query(), &["limit", "offset", "cursor"]);
+        let page = params.page();
+
+        params.finish()?;
+        Ok(page)
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Listing {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let keys = ["limit", "offset", "cursor", "sort", "fields", "include", "format", "report"];
+        let mut params = Params::parse(req.uri().query(), &keys);
+        let mut page = params.page();
+        page.sort = params.sort(&SortKey::ALL);
+        let projection = params.projection();
+        let output = params.output(accept(req), projection);
+
+        params.finish()?;
+        Ok(Listing { page, projection, output: output? })
+    }
+}
+
+// The decoded parameters of a query string along with everything wrong
+// with them, so a caller hears about all their mistakes at once.
+struct Params {
+    values: HashMap<String, String>,
+    errors: Vec<InvalidParam>,
+}
+
+impl Params {
+    fn query<T: Scope>(&mut self) -> QueryParams
+    {
+        let scrolled = self.values.contains_key("cursor");
+        let page = self.page();
+        let facets = self.facets(T::FACETS);
+        let filters = self.filters(T::FILTERS);
+
+        let query = match (self.text("query"), self.text("name")) {
+            (Some(..), Some(..)) => {
+                self.invalid("query", "only one of query, name may be given".into());
+                self.invalid("name", "only one of query, name may be given".into());
+                QueryType::All
+            },
+            (Some(query), None) => QueryType::Query(query),
+            (None, Some(name))  => QueryType::Name(name),
+            (None, None)        => QueryType::All,
+        };
+        if matches!(query, QueryType::Name(..)) {
+            if scrolled {
+                self.invalid("cursor", "not supported with name".into());
+            }
+            if !facets.is_empty() {
+                self.invalid("facets", "not supported with name".into());
+            }
+            if !filters.is_empty() {
+                self.invalid("name", "can't be combined with filters".into());
+            }
+        }
+
+        QueryParams { query, page, facets, filters, projection: Projection::ALL, output: Output::default() }
+    }
+
+    // Name matches come from autocomplete, which can't sort, so the service
+    // orders them itself and only by what the results carry.
+    fn sort_query(&mut self, query: &mut QueryParams, keys: &[SortKey])
+    {
+        let sort = self.sort(keys);
+        if let (QueryType::Name(..), Some(Sort { key: SortKey::LastModified, .. })) = (&query.query, sort) {
+            self.invalid("sort", "lastModified isn't supported with name".into());
+        }
+        query.page.sort = sort;
+    }
+
+    fn sort(&mut self, keys: &[SortKey]) -> Option<Sort>
+    {
+        let value = self.text("sort")?;
+        match value.parse::<Sort>() {
+            Ok(sort) if keys.contains(&sort.key) => Some(sort),
+            Ok(sort) => {
+                let names: Vec<_> = keys.iter().map(SortKey::name).collect();
+                self.invalid("sort", format!("can't sort by {}, expected one of {}", sort.key.name(), names.join(", ")));
+                None
+            },
+            Err(reason) => {
+                self.invalid("sort", reason);
+                None
+            }
+        }
+    }
+
+    // `format` wins over the `Accept` header, which is only refused with a
+    // 406 once the parameters are found to be fine.
+    fn output(&mut self, accept: Option<&str>, projection: Projection) -> Result<Output, ApiError>
+    {
+        let format = match self.text("format").map(|f| f.parse::<Format>()) {
+            Some(Ok(format)) => Ok(format),
+            Some(Err(reason)) => {
+                self.invalid("format", reason);
+                Ok(Format::Json)
+            },
+            None => Format::negotiate(accept)
+        };
+
+        let rows = match self.text("report").map(|r| r.parse::<Rows>()) {
+            Some(Ok(_)) if matches!(format, Ok(Format::Json)) => {
+                self.invalid("report", "only applies to csv and parquet".into());
+                Rows::Datasets
+            },
+            Some(Ok(rows)) => rows,
+            Some(Err(reason)) => {
+                self.invalid("report", reason);
+                Rows::Datasets
+            },
+            None => Rows::Datasets
+        };
+        if rows == Rows::Fields && !projection.wants("fields") {
+            self.invalid("report", "fields needs the schema aspect and fields field".into());
+        }
+
+        format.map(|format| Output { format, rows })
+    }
+
+    // `fields` picks the dataset fields to return and `include` the heavy
+    // aspects to fetch, all of them unless it says otherwise.
+    fn projection(&mut self) -> Projection
+    {
+        let mut projection = Projection::ALL;
+        let fields = self.list("fields");
+        let unknown: Vec<_> = fields.iter()
+            .filter(|f| !Projection::KEYS.contains(&f.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            let reason = format!("unknown {}, expected any of {}", unknown.join(", "), Projection::KEYS.join(", "));
+            self.invalid("fields", reason);
+        } else if !fields.is_empty() {
+            let fields: Vec<_> = fields.iter().map(String::as_str).collect();
+            projection = Projection::only(&fields);
+        }
+
+        let include = match self.text("include") {
+            Some(include) => include,
+            None => return projection
+        };
+        let aspects = Projection::ASPECTS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ");
+        let include: Vec<_> = include.split(',').map(str::trim).collect();
+        if include != ["none"] {
+            for name in include.iter().filter(|name| !Projection::ASPECTS.iter().any(|(aspect, _)| aspect == *name)) {
+                self.invalid("include", format!("unknown aspect {name}, expected any of {aspects} or none"));
+            }
+        }
+
+        for (aspect, keys) in Projection::ASPECTS {
+            if include.contains(&aspect) {
+                continue
+            }
+            let requested: Vec<_> = keys.iter().filter(|key| fields.iter().any(|f| f == *key)).copied().collect();
+            if !requested.is_empty() {
+                self.invalid("fields", format!("{} needs include={aspect}", requested.join(", ")));
+            }
+            projection = projection.without(keys);
+        }
+
+        projection
+    }
+
+    // Values in one filter parameter match any of them, except for tags
+    // with `tags_mode=all` where an entity must have every one.
+    fn filters(&mut self, allowed: &[&str]) -> Vec<Filter<'static>>
+    {
+        let all_tags = match self.text("tags_mode").as_deref() {
+            None | Some("any") => false,
+            Some("all") => true,
+            Some(..) => {
+                self.invalid("tags_mode", "must be any or all".into());
+                false
+            }
+        };
+        if all_tags && !self.values.contains_key("tags") && !self.values.contains_key("-tags") {
+            self.invalid("tags_mode", "only applies with tags".into());
+        }
+
+        let mut filters = Vec::new();
+        for (key, field, spell) in FILTERS.iter().filter(|(key, ..)| allowed.contains(key)) {
+            for negated in [false, true] {
+                let key = if negated { format!("-{key}") } else { key.to_string() };
+                let values: Vec<_> = self.list(&key).iter().map(|v| spell(v)).collect();
+                if values.is_empty() {
+                    continue
+                }
+
+                let group = if all_tags && *field == "tags" {
+                    values.into_iter().map(|v| Filter::any(field, vec![v])).collect()
+                } else {
+                    vec![Filter::any(field, values)]
+                };
+                filters.extend(group.into_iter().map(|f| if negated { f.negate() } else { f }));
+            }
+        }
+
+        filters
+    }
+
+    fn list(&mut self, key: &str) -> Vec<String>
+    {
+        self.text(key).iter()
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
+
+    fn parse(query: Option<&str>, allowed: &[&str]) -> Params
+    {
+        let mut params = Params { values: HashMap::new(), errors: Vec::new() };
+        let pairs = form_urlencoded::parse(query.unwrap_or("").as_bytes());
+
+        for (key, value) in pairs {
+            if !allowed.contains(&key.as_ref()) {
+                params.invalid(&key, format!("unknown parameter, expected one of {}", allowed.join(", ")));
+            } else if params.values.contains_key(key.as_ref()) {
+                params.invalid(&key, "given more than once".into());
+            } else {
+                params.values.insert(key.into_owned(), value.into_owned());
+            }
+        }
+
+        params
+    }
+
+    fn text(&mut self, key: &str) -> Option<String>
+    {
+        match self.values.remove(key) {
+            Some(value) if value.trim().is_empty() => {
+                self.invalid(key, "must not be empty".into());
+                None
+            },
+            value => value
+        }
+    }
+
+    fn number(&mut self, key: &str, default: i32, min: i32, max: i32) -> i32
+    {
+        let value = match self.values.remove(key) {
+            Some(value) => value,
+            None => return default
+        };
+
+        match value.parse::<i32>() {
+            Ok(n) if (min..=max).contains(&n) => n,
+            _ => {
+                self.invalid(key, format!("must be a whole number from {min} to {max}"));
+                default
+            }
+        }
+    }
+
+    fn facets(&mut self, allowed: &[Facet]) -> Vec<Facet>
+    {
+        let mut facets = Vec::new();
+        for name in self.list("facets") {
+            match allowed.iter().find(|f| f.name() == name) {
+                Some(facet) if facets.contains(facet) => {},
+                Some(facet) => facets.push(*facet),
+                None => {
+                    let names: Vec<_> = allowed.iter().map(Facet::name).collect();
+                    self.invalid("facets", format!("unknown facet {name}, expected one of {}", names.join(", ")));
+                }
+            }
+        }
+
+        facets
+    }
+
+    fn page(&mut self) -> Page
+    {
+        let limit = self.number("limit", DEFAULT_LIMIT, 1, MAX_LIMIT);
+        if self.values.contains_key("offset") && self.values.contains_key("cursor") {
+            self.invalid("offset", "only one of offset, cursor may be given".into());
+            self.invalid("cursor", "only one of offset, cursor may be given".into());
+            return Page { limit, start: 0, sort: None, cursor: None }
+        }
+
+        let issued = |c: &Cursor| c.offset >= 0 && c.scroll_id.is_some();
+        let cursor = match self.text("cursor") {
+            Some(token) => Cursor::decode(&token).filter(issued).or_else(|| {
+                self.invalid("cursor", "not a cursor from a previous page".into());
+                None
+            }),
+            None => None
+        };
+        if let Some(cursor) = cursor {
+            return Page { limit, start: cursor.offset, sort: None, cursor: Some(cursor) }
+        }
+
+        let start = self.number("offset", 0, 0, MAX_WINDOW - 1);
+        if start + limit > MAX_WINDOW {
+            self.invalid("offset", format!("offset plus limit must not exceed {MAX_WINDOW}; use cursor to go further"));
+        }
+
+        Page { limit, start, sort: None, cursor: None }
+    }
+
+    fn invalid(&mut self, name: &str, reason: String)
+    {
+        self.errors.push(InvalidParam { name: name.to_owned(), reason, position: None });
+    }
+
+    fn invalid_at(&mut self, name: &str, reason: String, position: usize)
+    {
+        self.errors.push(InvalidParam { name: name.to_owned(), reason, position: Some(position) });
+    }
+
+    fn finish(self) -> Result<(), ApiError>
+    {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::InvalidParams(self.errors))
+        }
+    }
+}
+
+fn accept<B>(req: &RequestParts<B>) -> Option<&str>
 {
-    query.split('&')
-        .map(|s| s.split('='))
-        .map(|mut v| {
-            let key = v.next().unwrap();
-            match v.next() {
-                Some(val) => (key, val),
-                None => ("query", key)
-            }
-        })
+    req.headers().get(header::ACCEPT).and_then(|value| value.to_str().ok())
+}
+
+// `keys` along with the `filters` parameters, their negations and, with
+// `tags`, `tags_mode`.
+fn with_filters(keys: &[&str], filters: &[&str]) -> Vec<String>
+{
+    let mode = filters.contains(&"tags").then_some("tags_mode");
+    let negated = filters.iter().map(|key| format!("-{key}"));
+
+    keys.iter().chain(filters).copied().chain(mode).map(str::to_owned)
+        .chain(negated)
         .collect()
 }
+
+fn urn(prefix: &str, value: &str) -> String
+{
+    if value.starts_with("urn:li:") {
+        value.to_owned()
+    } else {
+        format!("{prefix}{value}")
+    }
+}
+
+// DataHub's subtypes are capitalized, e.g. `View`.
+fn capitalize(value: &str) -> String
+{
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::{Request, StatusCode}, response::IntoResponse};
+    use serde_json::Value;
+
+    use super::*;
+
+
+    async fn extract<T: FromRequest<(), Rejection = ApiError>>(query: &str) -> Result<T, ApiError>
+    {
+        let req = Request::builder().uri(format!("/datasets?{query}")).body(()).expect("valid request");
+        T::from_request(&mut RequestParts::new(req)).await
+    }
+
+    fn accepted<T>(result: Result<T, ApiError>) -> T
+    {
+        result.unwrap_or_else(|err| panic!("rejected valid parameters: {err:?}"))
+    }
+
+    // Each rejected parameter and why.
+    fn rejected<T>(result: Result<T, ApiError>) -> Vec<(String, String)>
+    {
+        match result {
+            Err(ApiError::InvalidParams(params)) => params.into_iter().map(|p| (p.name, p.reason)).collect(),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(..) => panic!("accepted invalid parameters"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_parameters_are_rejected()
+    {
+        let reasons = rejected(extract::<Listed<Tags>>("query=orders&owner=bob").await);
+        assert_eq!(reasons, [(
+            "owner".to_owned(),
+            "unknown parameter, expected one of query, name, limit, offset, cursor, facets, sort".to_owned()
+        )]);
+    }
+
+    #[tokio::test]
+    async fn repeated_parameters_are_rejected()
+    {
+        let reasons = rejected(extract::<Listed<Tags>>("limit=5&limit=6").await);
+        assert_eq!(reasons, [("limit".to_owned(), "given more than once".to_owned())]);
+    }
+
+    #[tokio::test]
+    async fn limits_are_bounded()
+    {
+        let page = accepted(extract::<Page>("").await);
+        assert_eq!((page.limit, page.start), (DEFAULT_LIMIT, 0));
+        let page = accepted(extract::<Page>(&format!("limit={MAX_LIMIT}")).await);
+        assert_eq!(page.limit, MAX_LIMIT);
+
+        for limit in ["0", "1001", "-1", "ten", "1.5"] {
+            let reasons = rejected(extract::<Page>(&format!("limit={limit}")).await);
+            assert_eq!(reasons, [("limit".to_owned(), "must be a whole number from 1 to 1000".to_owned())]);
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_stay_inside_the_search_window()
+    {
+        let page = accepted(extract::<Page>("offset=9990&limit=10").await);
+        assert_eq!(page.start, 9_990);
+
+        let reasons = rejected(extract::<Page>("offset=9995&limit=10").await);
+        assert_eq!(reasons, [(
+            "offset".to_owned(),
+            "offset plus limit must not exceed 10000; use cursor to go further".to_owned()
+        )]);
+
+        let reasons = rejected(extract::<Page>("offset=10000").await);
+        assert_eq!(reasons, [("offset".to_owned(), "must be a whole number from 0 to 9999".to_owned())]);
+    }
+
+    #[tokio::test]
+    async fn facets_must_be_known()
+    {
+        let query = accepted(extract::<Listed<Tags>>("facets=platform,tags,platform").await).0;
+        assert_eq!(query.facets.len(), 2);
+
+        let reasons = rejected(extract::<Listed<Tags>>("facets=platform,owner").await);
+        assert_eq!(reasons, [(
+            "facets".to_owned(),
+            "unknown facet owner, expected one of platform, tags, origin, subtype".to_owned()
+        )]);
+    }
+
+    #[tokio::test]
+    async fn only_datasets_have_an_origin_or_subtype()
+    {
+        let query = accepted(extract::<Listed<Charts>>("platform=looker&-tags=pii&tags_mode=all&facets=tags").await).0;
+        assert_eq!((query.filters.len(), query.facets.len()), (2, 1));
+        let query = accepted(extract::<Filtered>("origin=PROD&type=view&facets=origin,subtype").await).0;
+        assert_eq!((query.filters.len(), query.facets.len()), (2, 2));
+
+        let reasons = rejected(extract::<Listed<Charts>>("facets=subtype").await);
+        assert_eq!(reasons, [("facets".to_owned(), "unknown facet subtype, expected one of platform, tags".to_owned())]);
+
+        for key in ["origin", "type", "-origin", "-type"] {
+            let reasons = rejected(extract::<Listed<Pipelines>>(&format!("{key}=PROD")).await);
+            assert_eq!(reasons, [(key.to_owned(), "unknown parameter, expected one of query, name, limit, \
+                offset, cursor, facets, sort, platform, tags, tags_mode, -platform, -tags".to_owned())]);
+        }
+        let reasons = rejected(extract::<Listed<Tags>>("platform=looker").await);
+        assert_eq!(reasons.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn every_mistake_is_reported_at_once()
+    {
+        let reasons = rejected(extract::<Listed<Tags>>("limit=0&facets=owner&query=a&name=b&sort=size").await);
+        let names: Vec<_> = reasons.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["limit", "facets", "query", "name", "sort"]);
+    }
+
+    #[tokio::test]
+    async fn rejections_are_problem_documents()
+    {
+        let err = match extract::<Listed<Tags>>("limit=0&owner=bob").await {
+            Err(err) => err,
+            Ok(..) => panic!("accepted invalid parameters"),
+        };
+        let resp = err.into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/problem+json");
+
+        let bytes = hyper::body::to_bytes(resp.into_body()).await.expect("readable body");
+        let problem: Value = serde_json::from_slice(&bytes).expect("JSON problem");
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["detail"], "2 invalid query parameter(s)");
+        let mut names: Vec<_> = problem["invalid-params"].as_array().expect("invalid-params").iter()
+            .map(|p| (p["name"].as_str().expect("name"), p["reason"].is_string()))
+            .collect();
+        names.sort();
+        assert_eq!(names, [("limit", true), ("owner", true)]);
+    }
+
+    #[tokio::test]
+    async fn values_are_percent_decoded()
+    {
+        let query = accepted(extract::<Listed<Tags>>("query=customer%20orders%2Bmore").await).0;
+        assert!(matches!(query.query, QueryType::Query(q) if q == "customer orders+more"));
+
+        let query = accepted(extract::<Listed<Tags>>("name=caf%C3%A9+bar").await).0;
+        assert!(matches!(query.query, QueryType::Name(n) if n == "café bar"));
+
+        let Filtered(query) = accepted(extract::<Filtered>("platform=urn%3Ali%3AdataPlatform%3Ahive").await);
+        assert_eq!(query.filters[0].values, ["urn:li:dataPlatform:hive"]);
+
+        let reasons = rejected(extract::<Listed<Tags>>("%6Cimit=0").await);
+        assert_eq!(reasons[0].0, "limit");
+    }
+}
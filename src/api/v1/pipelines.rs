@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, OriginalUri},
+    http::Uri,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::api::v1::{
+    extract::{Json, Path},
+    graphql::{
+        GetOneFactory,
+        ListFactory,
+        RelatedFactory,
+    },
+    cursor,
+    datasets::{query_values, TAGS_VALUES},
+    listing,
+    openapi::ApiRouter,
+    params::{Listed, RelatedParams},
+};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::Context;
+use crate::error::{ApiError, Problem};
+use crate::schemas::{
+    self,
+    datahub::Relationships,
+    Jobs,
+    Links,
+    Pipelines,
+    PipelineEnvelope,
+    Projection,
+    QueryResponse,
+};
+
+
+const PROPERTIES_VALUES: &str = "
+    orchestrator
+    cluster
+    properties {
+        name
+        description
+        project
+        url: externalUrl
+    }
+";
+
+static QUERY_VALUES: Lazy<String> = Lazy::new(|| {
+    format!("urn __typename ... on DataFlow {{ {PROPERTIES_VALUES} {TAGS_VALUES} }}")
+});
+
+static LIST: Lazy<ListFactory>          = Lazy::new(|| ListFactory::new("DATA_FLOW", &QUERY_VALUES));
+static GET_BY_ID: Lazy<GetOneFactory>   = Lazy::new(|| GetOneFactory::new("dataFlow", &QUERY_VALUES));
+
+/// The selection set for a job along with the datasets it reads and writes.
+pub fn job_values(projection: &Projection) -> String
+{
+    let datasets = query_values(projection);
+    format!(
+        "urn __typename ... on DataJob {{ \
+            properties {{ name description url: externalUrl }} \
+            io: inputOutput {{ \
+                inputs: inputDatasets {{ {datasets} }} \
+                outputs: outputDatasets {{ {datasets} }} \
+            }} \
+        }}"
+    )
+}
+
+/// The selection set for a page of a pipeline's jobs, which DataHub records
+/// as `IsPartOf` relationships from each job to its flow.
+fn flow_job_values(projection: &Projection) -> String
+{
+    format!(
+        "urn __typename ... on DataFlow {{ \
+            jobs: relationships(input: {{ types: [\"IsPartOf\"], direction: INCOMING, start: $start, count: $count }}) {{ \
+                total relationships {{ entity {{ {} }} }} \
+            }} \
+        }}",
+        job_values(projection)
+    )
+}
+
+/// A page of related jobs, projected and linked to the pages either side.
+pub fn jobs_page(uri: &Uri, params: &RelatedParams, rels: &Relationships) -> (Links, Jobs)
+{
+    let count = rels.relationships.len() as i32;
+    let links = cursor::offset_links(uri, &params.page, count, rels.total);
+    let jobs = Jobs::from(rels).project(params.projection).link(&links);
+
+    (links, jobs)
+}
+
+#[derive(Deserialize)]
+struct PipelineResponse<'a> {
+    #[serde(borrow)]
+    data: PipelineData<'a>,
+}
+
+#[derive(Deserialize)]
+struct PipelineData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::DataFlow<'a>>,
+}
+
+#[derive(Deserialize)]
+struct JobsResponse<'a> {
+    #[serde(borrow)]
+    data: JobsData<'a>,
+}
+
+#[derive(Deserialize)]
+struct JobsData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::RelatedJobs<'a>>,
+}
+
+pub fn routes(api: ApiRouter) -> ApiRouter
+{
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_jobs_by_pipeline, _, _>(jobs_by_pipeline)
+}
+
+#[utoipa::path(
+    get,
+    path = "/pipelines/{id}",
+    tag = "pipelines",
+    summary = "Pipeline with the specified id",
+    params(("id" = String, Path, description = "Data flow urn")),
+    responses(
+        (status = 200, description = "The pipeline", body = PipelineEnvelope),
+        (status = 404, description = "No such pipeline", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_id(
+    Path(id): Path<String>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<PipelineEnvelope>), ApiError>
+{
+    let body = GET_BY_ID.body(&id);
+    let (status, bytes) = cache.fetch(Route::Pipeline, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: PipelineResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(flow) => Ok((status, PipelineEnvelope::from(&flow).into())),
+        None => Err(ApiError::NotFound(id))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/pipelines",
+    tag = "pipelines",
+    summary = "All pipelines, or those matching a query or name",
+    params(
+        ("query" = Option<String>, Query, description = "Pipelines with any value like this"),
+        ("name" = Option<String>, Query, description = "Pipelines with names like this, up to limit (not paged)"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of pipelines, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of pipelines to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("platform" = Option<String>, Query, description = "Pipelines on any of these comma separated platforms"),
+        ("tags" = Option<String>, Query, description = "Pipelines with any (or with tags_mode=all, every one) of these comma separated tags"),
+        ("tags_mode" = Option<String>, Query, description = "How tags combine: any (default) or all"),
+        ("-platform" = Option<String>, Query, description = "Pipelines on none of these platforms"),
+        ("-tags" = Option<String>, Query, description = "Pipelines without these tags"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags (not with name)"),
+        ("sort" = Option<String>, Query, description = "name, or -name for descending order (default relevance)"),
+    ),
+    responses(
+        (status = 200, description = "A page of pipelines", body = Pipelines, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown, conflicting or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn by_query(
+    Listed(params, ..): Listed<Pipelines>,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<Pipelines>), ApiError>
+{
+    let (status, bytes) = listing::fetch(Route::Pipelines, &LIST, &params, &backend, &cache, &ctx).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let (links, pipelines) = listing::page::<Pipelines>(&uri, &params, &body);
+
+    Ok((status, links, pipelines.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/pipelines/{id}/jobs",
+    tag = "pipelines",
+    summary = "Jobs of the specified pipeline with their input and output datasets",
+    params(
+        ("id" = String, Path, description = "Data flow urn"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of jobs, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of jobs to skip (default 0); offset plus limit may not exceed 10000"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields of the input and output datasets to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated dataset aspects to fetch: schema, tags, platform or none (default all)"),
+    ),
+    responses(
+        (status = 200, description = "A page of the pipeline's jobs", body = Jobs, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "No such pipeline", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+async fn jobs_by_pipeline(
+    Path(id): Path<String>,
+    params: RelatedParams,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<Jobs>), ApiError>
+{
+    let factory = RelatedFactory::new("dataFlow", &flow_job_values(&params.projection));
+    let body = factory.body(&id, &params.page);
+    let (status, bytes) = cache.fetch(Route::PipelineJobs, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: JobsResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(flow) => {
+            let (links, jobs) = jobs_page(&uri, &params, &flow.jobs);
+            Ok((status, links, jobs.into()))
+        },
+        None => Err(ApiError::NotFound(id))
+    }
+}
@@ -1,27 +1,36 @@
+use std::sync::Arc;
+
 use axum::{
-    Json, Router,
-    extract::{Extension, Path},
-    http::Request,
-    routing::get,
+    extract::{Extension, OriginalUri},
+    response::Response,
 };
-use hyper::{client::HttpConnector, Body};
+use futures_util::{StreamExt, TryStreamExt, stream};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 
-use crate::{datahub::{post, GRAPHQL_ENDPOINT}};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::Context;
+use crate::error::{ApiError, Problem};
 use crate::schemas::{
     self,
+    Collection,
     Datasets,
     Platforms,
     PlatformEnvelope,
+    CountResponse,
+    Links,
     QueryResponse,
-    ListRecommendationsResponse
 };
 
 use crate::api::v1::{
-    graphql::{GetOneFactory, FilterFactory, PlatformsFactory},
-    datasets::QUERY_VALUES as DATASET_VALUES,
-    params::QueryParams
+    extract::{Json, Path},
+    cursor,
+    graphql::{CountFactory, GetAllFactory, GetOneFactory, FilterFactory},
+    datasets::query_values,
+    openapi::ApiRouter,
+    params::{Listing, Page},
+    report,
 };
 
 const QUERY_VALUES: &str = "
@@ -32,82 +41,178 @@ const QUERY_VALUES: &str = "
         properties {
             name: displayName
             class: type
+            logo: logoUrl
         }
     }
 ";
 
-static GET_ALL: Lazy<PlatformsFactory> = Lazy::new(|| PlatformsFactory::new(QUERY_VALUES));
-static GET_BY_ID: Lazy<GetOneFactory>  = Lazy::new(|| GetOneFactory::new("dataPlatform", QUERY_VALUES));
-static DATASETS_BY_PLATFORM: Lazy<FilterFactory>
-    = Lazy::new(|| FilterFactory::new("DATASETS", DATASET_VALUES, "platform"));
+// How many dataset counts of a page are fetched from DataHub at once.
+const CONCURRENT_COUNTS: usize = 8;
 
-type Client = hyper::client::Client<HttpConnector, Body>;
+static GET_ALL: Lazy<GetAllFactory>   = Lazy::new(|| GetAllFactory::new("DATA_PLATFORM", QUERY_VALUES));
+static GET_BY_ID: Lazy<GetOneFactory> = Lazy::new(|| GetOneFactory::new("dataPlatform", QUERY_VALUES));
+static DATASET_COUNT: Lazy<CountFactory> = Lazy::new(|| CountFactory::new("DATASET", "platform"));
 
 
 #[derive(Deserialize)]
 struct PlatformResponse<'a> {
     #[serde(borrow)]
-    data: schemas::datahub::PlatformEntity<'a>,
+    data: PlatformData<'a>,
+}
+
+#[derive(Deserialize)]
+struct PlatformData<'a> {
+    #[serde(borrow)]
+    entity: Option<schemas::datahub::DataPlatform<'a>>,
 }
 
-pub fn routes() -> Router
+pub fn routes(api: ApiRouter) -> ApiRouter
 {
-    Router::new()
-        .route("/", get(by_query))
-        .route("/:id", get(by_id))
-        .route("/:id/datasets", get(datasets_by_platform))
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_datasets_by_platform, _, _>(datasets_by_platform)
 }
 
+#[utoipa::path(
+    get,
+    path = "/platforms/{id}",
+    tag = "platforms",
+    summary = "Data platform with the specified id",
+    params(("id" = String, Path, description = "Data platform urn")),
+    responses(
+        (status = 200, description = "The data platform", body = PlatformEnvelope),
+        (status = 404, description = "No such data platform", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_id(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-) -> Json<PlatformEnvelope>
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<PlatformEnvelope>), ApiError>
 {
     let body = GET_BY_ID.body(&id);
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: PlatformResponse = serde_json::from_slice(&bytes).unwrap();
-
-    PlatformEnvelope::from(&body.data).into()
+    let count = DATASET_COUNT.body(&id);
+    let ((status, bytes), (counted, count_bytes)) = tokio::try_join!(
+        cache.fetch(Route::Platform, &ctx, &body, || backend.get(&ctx, &body)),
+        cache.fetch(Route::Platform, &ctx, &count, || backend.search(&ctx, &count)),
+    )?;
+    let body: PlatformResponse = serde_json::from_slice(&bytes)?;
+    let count: CountResponse = serde_json::from_slice(&count_bytes)?;
+
+    match body.data.entity {
+        Some(platform) => {
+            let platform = PlatformEnvelope::from(&platform).with_count(&count);
+            Ok((status.and(counted), platform.into()))
+        },
+        None => Err(ApiError::NotFound(id))
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/platforms",
+    tag = "platforms",
+    summary = "All data platforms with their dataset counts",
+    params(
+        ("limit" = Option<i32>, Query, description = "Maximum number of data platforms, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of data platforms to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+    ),
+    responses(
+        (status = 200, description = "A page of data platforms", body = Platforms, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_query(
-    Extension(client): Extension<Client>,
-    req: Request<Body>
-) -> Json<schemas::Platforms>
+    page: Page,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<schemas::Platforms>), ApiError>
 {
-    let params = QueryParams::from(&req);
-    let body = GET_ALL.body(&params);
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: ListRecommendationsResponse = serde_json::from_slice(&bytes).unwrap();
-
-    Platforms::from(&body).into()
+    let body = GET_ALL.body(&page, &[]);
+    let (status, bytes) = cache.fetch(Route::Platforms, &ctx, &body, || cursor::search(&backend, &ctx, &page, &body)).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let platforms = Platforms::from(&body);
+    let (counted, counts) = dataset_counts(&platforms.ids(), &backend, &cache, &ctx).await?;
+    let links = cursor::links(&uri, &page, &body);
+    let platforms = platforms.with_counts(&counts).link(&links);
+
+    Ok((status.and(counted), links, platforms.into()))
 }
 
+// DataHub only returns the largest buckets of a facet, so each platform's
+// datasets are counted by a search of their own.
+async fn dataset_counts(
+    ids: &[&str],
+    backend: &Backend,
+    cache: &Cache,
+    ctx: &Context
+) -> Result<(CacheStatus, Vec<CountResponse>), ApiError>
+{
+    let bodies: Vec<_> = ids.iter().map(|id| DATASET_COUNT.body(id)).collect();
+    let fetches: Vec<_> = bodies.iter()
+        .map(|body| cache.fetch(Route::Platforms, ctx, body, || backend.search(ctx, body)))
+        .collect();
+    let fetched: Vec<_> = stream::iter(fetches)
+        .buffered(CONCURRENT_COUNTS)
+        .try_collect()
+        .await?;
+
+    let mut status = CacheStatus::Hit;
+    let mut counts = Vec::with_capacity(fetched.len());
+    for (counted, bytes) in fetched {
+        status = status.and(counted);
+        counts.push(serde_json::from_slice(&bytes)?);
+    }
+
+    Ok((status, counts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/platforms/{id}/datasets",
+    tag = "platforms",
+    summary = "Datasets on the specified data platform",
+    params(
+        ("id" = String, Path, description = "Data platform urn"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of datasets, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of datasets to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order (default relevance)"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+        ("format" = Option<String>, Query, description = "json (default), csv or parquet, for this page; overrides the Accept header"),
+        ("report" = Option<String>, Query, description = "With csv or parquet, a row per dataset (datasets, the default) or per schema field (fields)"),
+    ),
+    responses(
+        (status = 200, description = "A page of datasets, as JSON or a report", content(
+            (Datasets = "application/json"),
+            (String = "text/csv"),
+            (Vec<u8> = "application/vnd.apache.parquet"),
+        ), headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The Accept header names no format the page can be sent as", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn datasets_by_platform(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-    req: Request<Body>
-) -> Json<schemas::Datasets>
+    Listing { page, projection, output }: Listing,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Response), ApiError>
 {
-    let params = QueryParams::from(&req);
-    let body = DATASETS_BY_PLATFORM.body(&id, &params);
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: QueryResponse = serde_json::from_slice(&bytes).unwrap();
-
-    Datasets::from(&body).into()
+    let factory = FilterFactory::new("DATASET", &query_values(&projection), "platform");
+    let body = factory.body(&id, &page);
+    let (status, bytes) = cache.fetch(Route::PlatformDatasets, &ctx, &body, || cursor::search(&backend, &ctx, &page, &body)).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let links = cursor::links(&uri, &page, &body);
+    let datasets = Datasets::from(&body).link(&links).project(projection);
+
+    Ok((status, links, report::render(&output, datasets, projection)?))
 }
@@ -8,6 +8,16 @@ pub fn by_id(entity: &str, values: &str) -> String
     "#).replace('\n', "").replace("  ", " ")
 }
 
+// `values` pages the entity's relationships with `$start` and `$count`.
+pub fn related(entity: &str, values: &str) -> String
+{
+    format!(r#"
+        query related($urn: String!, $start: Int!, $count: Int!) {{
+            entity: {entity}(urn: $urn) {{ {values} }}
+        }}
+    "#).replace('\n', "").replace("  ", " ")
+}
+
 pub fn by_name(values: &str) -> String
 {
     format!(r#"
@@ -27,11 +37,35 @@ pub fn by_query(values: &str) -> String
             results: search(input: $input) {{
                 __typename start count total
                 entities: searchResults {{ entity {{ {values} }} }}
+                facets {{ field aggregations {{ value count }} }}
             }}
         }}
     "#).replace('\n', "").replace("  ", " ")
 }
 
+pub fn by_scroll(values: &str) -> String
+{
+    format!(r#"
+        query by_scroll($input: ScrollAcrossEntitiesInput!) {{
+            results: scrollAcrossEntities(input: $input) {{
+                __typename next: nextScrollId count total
+                entities: searchResults {{ entity {{ {values} }} }}
+                facets {{ field aggregations {{ value count }} }}
+            }}
+        }}
+    "#).replace('\n', "").replace("  ", " ")
+}
+
+// Only the scroll id after the results, not the results themselves.
+pub fn seek() -> String
+{
+    r#"
+        query seek($input: ScrollAcrossEntitiesInput!) {
+            results: scrollAcrossEntities(input: $input) { next: nextScrollId }
+        }
+    "#.replace('\n', "").replace("  ", " ")
+}
+
 pub fn add_tag() -> String
 {
     "mutation add_tag($input: TagAssociationInput!) {
@@ -46,16 +80,11 @@ pub fn remove_tag() -> String
     }".replace('\n', "")
 }
 
-pub fn platforms(values: &str) -> String
+pub fn count() -> String
 {
-    format!(r#"
-        query platforms($input: ListRecommendationsInput!) {{ 
-            results: listRecommendations(input: $input) {{
-                modules {{
-                    id: moduleId
-                    content {{ entity {{ {values} }} }}
-                }}
-            }}
-        }}
-    "#).replace('\n', "").replace("  ", " ")
-}
\ No newline at end of file
+    r#"
+        query count($input: SearchInput!) {
+            results: search(input: $input) { total }
+        }
+    "#.replace('\n', "").replace("  ", " ")
+}
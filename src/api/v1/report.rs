@@ -0,0 +1,288 @@
+use std::{str::FromStr, sync::Arc};
+
+use axum::{
+    Json,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    data_type::{ByteArray, ByteArrayType},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::types::Type,
+};
+
+use crate::error::ApiError;
+use crate::schemas::{datasets::Table, Datasets, Projection};
+
+
+/// How a dataset listing is sent: as JSON, or as a CSV or Parquet report.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Parquet,
+}
+
+/// What a report has a row for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rows {
+    Datasets,
+    Fields,
+}
+
+pub struct Output {
+    pub format: Format,
+    pub rows: Rows,
+}
+
+impl Default for Output {
+    fn default() -> Self
+    {
+        Output { format: Format::Json, rows: Rows::Datasets }
+    }
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Json, Format::Csv, Format::Parquet];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Format::Json    => "json",
+            Format::Csv     => "csv",
+            Format::Parquet => "parquet",
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str
+    {
+        match self {
+            Format::Json    => "application/json",
+            Format::Csv     => "text/csv",
+            Format::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    /// The first format an `Accept` header names, ignoring any weights;
+    /// `*/*` and `application/*` stand for JSON and `text/*` for CSV.
+    pub fn accepted(accept: &str) -> Option<Format>
+    {
+        accept.split(',')
+            .map(|range| range.split(';').next().unwrap_or_default().trim().to_ascii_lowercase())
+            .find_map(|media_type| match media_type.as_str() {
+                "*/*" | "application/*" => Some(Format::Json),
+                "text/*" => Some(Format::Csv),
+                _ => Format::ALL.into_iter().find(|f| f.media_type() == media_type),
+            })
+    }
+
+    /// The format to send for an `Accept` header, JSON without one, or a
+    /// 406 when it names nothing a dataset listing can be sent as.
+    pub fn negotiate(accept: Option<&str>) -> Result<Format, ApiError>
+    {
+        match accept.map(str::trim) {
+            None | Some("") => Ok(Format::Json),
+            Some(accept) => Format::accepted(accept).ok_or_else(|| ApiError::NotAcceptable(accept.to_owned())),
+        }
+    }
+}
+
+impl Rows {
+    pub const ALL: [Rows; 2] = [Rows::Datasets, Rows::Fields];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Rows::Datasets => "datasets",
+            Rows::Fields   => "fields",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        Format::ALL.into_iter()
+            .find(|f| f.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Format::ALL.iter().map(Format::name).collect();
+                format!("unknown format {s}, expected one of {}", names.join(", "))
+            })
+    }
+}
+
+impl FromStr for Rows {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        Rows::ALL.into_iter()
+            .find(|r| r.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Rows::ALL.iter().map(Rows::name).collect();
+                format!("unknown report {s}, expected one of {}", names.join(", "))
+            })
+    }
+}
+
+/// The page of datasets as JSON, or flattened into the requested report.
+/// A report covers only this page, like the JSON would; the `Link` header
+/// leads to the next one.
+pub fn render(output: &Output, datasets: Datasets, projection: Projection) -> Result<Response, ApiError>
+{
+    let mut resp = match (output.format, output.rows) {
+        (Format::Json, _) => Json(datasets).into_response(),
+        (format, rows) => report(format, rows, datasets, projection)?,
+    };
+    // The same URL may be sent in any format, so caches must tell them apart.
+    resp.headers_mut().insert(header::VARY, HeaderValue::from_static("accept"));
+
+    Ok(resp)
+}
+
+fn report(format: Format, rows: Rows, datasets: Datasets, projection: Projection) -> Result<Response, ApiError>
+{
+    let table = match rows {
+        Rows::Datasets => datasets.dataset_table(projection),
+        Rows::Fields   => datasets.field_table(),
+    };
+    let bytes = match format {
+        Format::Parquet => parquet(&table)?,
+        _ => csv(&table)?,
+    };
+
+    let filename = format!("attachment; filename=\"{}.{}\"", rows.name(), format.name());
+    let mut resp = bytes.into_response();
+    let headers = resp.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(format.media_type()));
+    if let Ok(value) = HeaderValue::from_str(&filename) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+
+    Ok(resp)
+}
+
+// Missing values are empty cells, as are empty lists.
+fn csv(table: &Table) -> Result<Vec<u8>, ApiError>
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&table.columns)?;
+    for row in &table.rows {
+        writer.write_record(row.iter().map(|value| value.as_deref().unwrap_or_default()))?;
+    }
+
+    writer.into_inner().map_err(|e| ApiError::Encode(e.to_string()))
+}
+
+// Every column is an optional string, written as a single row group.
+fn parquet(table: &Table) -> Result<Vec<u8>, ApiError>
+{
+    let fields = table.columns.iter()
+        .map(|name| Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(Some(LogicalType::String))
+            .build()
+            .map(Arc::new))
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Type::group_type_builder("report").with_fields(fields).build()?;
+
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), props)?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        let values: Vec<ByteArray> = table.rows.iter()
+            .filter_map(|row| row[index].as_deref())
+            .map(ByteArray::from)
+            .collect();
+        let levels: Vec<i16> = table.rows.iter()
+            .map(|row| i16::from(row[index].is_some()))
+            .collect();
+        column.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)?;
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+
+    Ok(writer.into_inner()?)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use hyper::body::Bytes;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+
+    use super::*;
+
+
+    fn table() -> Table
+    {
+        Table {
+            columns: vec!["id", "name", "description"],
+            rows: vec![
+                vec![Some("urn:1".into()), Some("orders, daily".into()), Some("says \"hi\"\nthen bye".into())],
+                vec![Some("urn:2".into()), None, Some(String::new())],
+            ],
+        }
+    }
+
+    #[test]
+    fn accept_headers_pick_a_format()
+    {
+        let negotiated = |accept: Option<&str>| Format::negotiate(accept).ok().map(|f| f.name());
+        assert_eq!(negotiated(None), Some("json"));
+        assert_eq!(negotiated(Some(" ")), Some("json"));
+        assert_eq!(negotiated(Some("*/*")), Some("json"));
+        assert_eq!(negotiated(Some("application/*;q=0.5")), Some("json"));
+        assert_eq!(negotiated(Some("text/*")), Some("csv"));
+        assert_eq!(negotiated(Some("Text/CSV; charset=utf-8")), Some("csv"));
+        assert_eq!(negotiated(Some("image/png, application/vnd.apache.parquet, text/csv")), Some("parquet"));
+
+        for accept in ["application/xml", "image/*, text/html", "csv"] {
+            match Format::negotiate(Some(accept)) {
+                Err(err @ ApiError::NotAcceptable(..)) => assert_eq!(err.status(), StatusCode::NOT_ACCEPTABLE),
+                _ => panic!("accepted {accept}"),
+            }
+        }
+    }
+
+    #[test]
+    fn csv_quotes_what_needs_it()
+    {
+        let bytes = csv(&table()).expect("CSV report");
+        assert_eq!(
+            String::from_utf8(bytes).expect("UTF-8"),
+            "id,name,description\nurn:1,\"orders, daily\",\"says \"\"hi\"\"\nthen bye\"\nurn:2,,\n"
+        );
+    }
+
+    #[test]
+    fn parquet_columns_are_optional_strings()
+    {
+        let bytes = parquet(&table()).expect("Parquet report");
+        let reader = SerializedFileReader::new(Bytes::from(bytes)).expect("Parquet file");
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let columns: Vec<_> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(columns, ["id", "name", "description"]);
+        for column in schema.columns() {
+            let column = column.self_type();
+            assert_eq!(column.get_basic_info().repetition(), Repetition::OPTIONAL);
+            assert_eq!(column.get_physical_type(), PhysicalType::BYTE_ARRAY);
+            assert_eq!(column.get_basic_info().logical_type(), Some(LogicalType::String));
+        }
+
+        let rows: Vec<Vec<Field>> = reader.get_row_iter(None).expect("rows")
+            .map(|row| row.expect("row").get_column_iter().map(|(_, field)| field.clone()).collect())
+            .collect();
+        assert_eq!(rows[1], [Field::Str("urn:2".into()), Field::Null, Field::Str(String::new())]);
+        assert_eq!(rows[0][2], Field::Str("says \"hi\"\nthen bye".into()));
+    }
+}
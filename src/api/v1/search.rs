@@ -0,0 +1,528 @@
+use crate::api::v1::params::FILTERS;
+use crate::schemas::{Condition, Filter};
+
+
+// The most alternative filter groups a query may expand to; NOT and OR
+// multiply out, so a handful of parentheses can otherwise explode.
+const MAX_GROUPS: usize = 32;
+
+const FIELDS: &str = "platform, tag, origin, type, name, field";
+
+/// A search box query such as
+/// `platform:snowflake AND tag:pii AND NOT name:tmp_* customer` compiled
+/// into DataHub's search text plus filter groups, any one of which an
+/// entity must satisfy in full.
+pub struct Search {
+    pub text: String,
+    pub filters: Vec<Vec<Filter<'static>>>,
+}
+
+impl Default for Search {
+    fn default() -> Self
+    {
+        Search { text: "*".to_owned(), filters: vec![] }
+    }
+}
+
+/// What's wrong with a query and the character offset where it went wrong.
+pub struct SyntaxError {
+    pub position: usize,
+    pub reason: String,
+}
+
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term { field: Option<String>, value: String, quoted: bool },
+}
+
+enum Expr {
+    Text { value: String, quoted: bool, at: usize },
+    Match { filter: Filter<'static>, at: usize },
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+pub fn parse(query: &str) -> Result<Search, SyntaxError>
+{
+    let tokens = lex(query)?;
+    let mut parser = Parser { tokens, next: 0, end: query.chars().count() };
+    let expr = parser.or()?;
+    if let Some((at, _)) = parser.peek() {
+        return Err(error(at, "unexpected ')'"))
+    }
+
+    let conjuncts = match expr {
+        Expr::And(items) => items,
+        expr => vec![expr]
+    };
+
+    // Free text goes to DataHub as the search text; everything else must
+    // hold as well, so its groups multiply with those before it.
+    let mut text = Vec::new();
+    let mut groups = vec![vec![]];
+    for expr in conjuncts {
+        match expr {
+            Expr::Text { value, quoted: true, .. } => text.push(format!("\"{value}\"")),
+            Expr::Text { value, .. } => text.push(value),
+            expr => groups = product(groups, dnf(&expr, false)?, expr.at())?,
+        }
+    }
+    groups.retain(|group| !group.is_empty());
+
+    let text = if text.is_empty() { "*".to_owned() } else { text.join(" ") };
+    Ok(Search { text, filters: groups })
+}
+
+fn lex(query: &str) -> Result<Vec<(usize, Token)>, SyntaxError>
+{
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => { i += 1; continue },
+            '(' => { i += 1; tokens.push((start, Token::Open)) },
+            ')' => { i += 1; tokens.push((start, Token::Close)) },
+            '-' => { i += 1; tokens.push((start, Token::Not)) },
+            '"' => {
+                let value = quoted(&chars, &mut i)?;
+                tokens.push((start, Token::Term { field: None, value, quoted: true }));
+            },
+            _ => {
+                let word = take(&chars, &mut i, |c| c == ':');
+                if i < chars.len() && chars[i] == ':' {
+                    i += 1;
+                    let (value, quoted) = if i < chars.len() && chars[i] == '"' {
+                        (quoted(&chars, &mut i)?, true)
+                    } else {
+                        (take(&chars, &mut i, |_| false), false)
+                    };
+                    if value.is_empty() {
+                        return Err(error(i, &format!("expected a value after '{word}:'")))
+                    }
+                    tokens.push((start, Token::Term { field: Some(word), value, quoted }));
+                } else {
+                    let token = match word.as_str() {
+                        "AND" => Token::And,
+                        "OR"  => Token::Or,
+                        "NOT" => Token::Not,
+                        _ => Token::Term { field: None, value: word, quoted: false }
+                    };
+                    tokens.push((start, token));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// The characters up to whitespace, a parenthesis or `stop`.
+fn take(chars: &[char], i: &mut usize, stop: impl Fn(char) -> bool) -> String
+{
+    let start = *i;
+    while *i < chars.len() && !chars[*i].is_whitespace() && !matches!(chars[*i], '(' | ')') && !stop(chars[*i]) {
+        *i += 1;
+    }
+
+    chars[start..*i].iter().collect()
+}
+
+// A double quoted string, where a backslash escapes the next character.
+fn quoted(chars: &[char], i: &mut usize) -> Result<String, SyntaxError>
+{
+    let open = *i;
+    let mut value = String::new();
+    *i += 1;
+    while *i < chars.len() {
+        match chars[*i] {
+            '"' => {
+                *i += 1;
+                return Ok(value)
+            },
+            '\\' if *i + 1 < chars.len() => {
+                value.push(chars[*i + 1]);
+                *i += 2;
+            },
+            c => {
+                value.push(c);
+                *i += 1;
+            }
+        }
+    }
+
+    Err(error(open, "unterminated quote"))
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, &Token)>
+    {
+        self.tokens.get(self.next).map(|(at, token)| (*at, token))
+    }
+
+    fn here(&self) -> usize
+    {
+        self.peek().map_or(self.end, |(at, _)| at)
+    }
+
+    fn or(&mut self) -> Result<Expr, SyntaxError>
+    {
+        let mut items = vec![self.and()?];
+        while let Some((_, Token::Or)) = self.peek() {
+            self.next += 1;
+            items.push(self.and()?);
+        }
+
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::Or(items) })
+    }
+
+    // Terms side by side are ANDed just as if AND sat between them.
+    fn and(&mut self) -> Result<Expr, SyntaxError>
+    {
+        let mut items = vec![self.primary()?];
+        loop {
+            match self.peek() {
+                Some((_, Token::And)) => {
+                    self.next += 1;
+                    items.push(self.primary()?);
+                },
+                Some((_, Token::Open | Token::Not | Token::Term { .. })) => items.push(self.primary()?),
+                _ => break
+            }
+        }
+
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::And(items) })
+    }
+
+    fn primary(&mut self) -> Result<Expr, SyntaxError>
+    {
+        let at = self.here();
+        let token = match self.tokens.get_mut(self.next) {
+            Some((_, token)) => std::mem::replace(token, Token::Close),
+            None => return Err(error(at, "expected a search term"))
+        };
+        self.next += 1;
+
+        match token {
+            Token::Open => {
+                let expr = self.or()?;
+                match self.peek() {
+                    Some((_, Token::Close)) => {
+                        self.next += 1;
+                        Ok(expr)
+                    },
+                    _ => Err(error(at, "unclosed '('"))
+                }
+            },
+            Token::Term { field: None, value, quoted } => Ok(Expr::Text { value, quoted, at }),
+            Token::Term { field: Some(field), value, .. } => {
+                Ok(Expr::Match { filter: filter(&field, &value, at)?, at })
+            },
+            Token::Close => Err(error(at, "unexpected ')'")),
+            Token::And => Err(error(at, "expected a search term before AND")),
+            Token::Or  => Err(error(at, "expected a search term before OR")),
+            Token::Not => Ok(Expr::Not(Box::new(self.primary()?))),
+        }
+    }
+}
+
+fn filter(field: &str, value: &str, at: usize) -> Result<Filter<'static>, SyntaxError>
+{
+    let key = match field {
+        "tag" => "tags",
+        field => field
+    };
+
+    match key {
+        "name"  => wildcard("name", value, at),
+        "field" => wildcard("fieldPaths", value, at),
+        _ => match FILTERS.iter().find(|(name, ..)| *name == key) {
+            Some(_) if value.contains('*') => {
+                Err(error(at, &format!("wildcards only apply to name and field, not {field}")))
+            },
+            Some((_, field, spell)) => Ok(Filter::new(field, &spell(value))),
+            None => Err(error(at, &format!("unknown field '{field}', expected one of {FIELDS}")))
+        }
+    }
+}
+
+// A leading and/or trailing `*` matches any characters there.
+fn wildcard(field: &'static str, value: &str, at: usize) -> Result<Filter<'static>, SyntaxError>
+{
+    let (starts, ends) = (value.starts_with('*'), value.ends_with('*') && value.len() > 1);
+    let inner = value.trim_start_matches('*').trim_end_matches('*');
+    if inner.is_empty() || inner.contains('*') {
+        return Err(error(at, "wildcards may only start or end a value"))
+    }
+
+    let condition = match (starts, ends) {
+        (true, true)   => Condition::Contain,
+        (true, false)  => Condition::EndWith,
+        (false, true)  => Condition::StartWith,
+        (false, false) => Condition::Equal,
+    };
+
+    Ok(Filter::matching(field, inner.to_owned(), condition))
+}
+
+// The expression as groups of filters, any one of which must hold in full,
+// with NOT pushed down onto the filters themselves.
+fn dnf(expr: &Expr, negated: bool) -> Result<Vec<Vec<Filter<'static>>>, SyntaxError>
+{
+    match (expr, negated) {
+        (Expr::Text { at, .. }, _) => {
+            Err(error(*at, "free text can only be ANDed with the rest of the query"))
+        },
+        (Expr::Match { filter, .. }, negated) => {
+            let filter = if negated { filter.clone().negate() } else { filter.clone() };
+            Ok(vec![vec![filter]])
+        },
+        (Expr::Not(inner), negated) => dnf(inner, !negated),
+        (Expr::And(items), false) | (Expr::Or(items), true) => {
+            items.iter().try_fold(vec![vec![]], |groups, item| {
+                product(groups, dnf(item, negated)?, expr.at())
+            })
+        },
+        (Expr::Or(items), false) | (Expr::And(items), true) => {
+            let mut groups = Vec::new();
+            for item in items {
+                groups.extend(dnf(item, negated)?);
+            }
+            if groups.len() > MAX_GROUPS {
+                return Err(error(expr.at(), &format!("expands to more than {MAX_GROUPS} alternatives")))
+            }
+            Ok(groups)
+        },
+    }
+}
+
+fn product(
+    left: Vec<Vec<Filter<'static>>>,
+    right: Vec<Vec<Filter<'static>>>,
+    at: usize
+) -> Result<Vec<Vec<Filter<'static>>>, SyntaxError>
+{
+    if left.len() * right.len() > MAX_GROUPS {
+        return Err(error(at, &format!("expands to more than {MAX_GROUPS} alternatives")))
+    }
+
+    Ok(left.iter()
+        .flat_map(|l| right.iter().map(move |r| [l.as_slice(), r.as_slice()].concat()))
+        .collect())
+}
+
+impl Expr {
+    fn at(&self) -> usize
+    {
+        match self {
+            Expr::Text { at, .. } | Expr::Match { at, .. } => *at,
+            Expr::Not(inner) => inner.at(),
+            Expr::And(items) | Expr::Or(items) => items[0].at(),
+        }
+    }
+}
+
+fn error(position: usize, reason: &str) -> SyntaxError
+{
+    SyntaxError { position, reason: reason.to_owned() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn search(query: &str) -> Search
+    {
+        match parse(query) {
+            Ok(search) => search,
+            Err(err) => panic!("{query:?} failed at {}: {}", err.position, err.reason)
+        }
+    }
+
+    // Each group's filters as `field=value`, with `-` for negated ones and
+    // `^=`, `$=` or `*=` for starts, ends with and contains.
+    fn groups(query: &str) -> Vec<Vec<String>>
+    {
+        search(query).filters.iter()
+            .map(|group| group.iter().map(|f| {
+                let op = match f.condition {
+                    Condition::Equal     => "=",
+                    Condition::StartWith => "^=",
+                    Condition::EndWith   => "$=",
+                    Condition::Contain   => "*=",
+                };
+                format!("{}{}{op}{}", if f.negated { "-" } else { "" }, f.field, f.values.join(","))
+            }).collect())
+            .collect()
+    }
+
+    fn error(query: &str) -> (usize, String)
+    {
+        match parse(query) {
+            Ok(_) => panic!("{query:?} parsed"),
+            Err(err) => (err.position, err.reason)
+        }
+    }
+
+    const HIVE: &str = "platform=urn:li:dataPlatform:hive";
+    const KAFKA: &str = "platform=urn:li:dataPlatform:kafka";
+    const PII: &str = "tags=urn:li:tag:pii";
+    const GDPR: &str = "tags=urn:li:tag:gdpr";
+
+    #[test]
+    fn free_text_is_the_search_text()
+    {
+        let s = search("customer orders");
+        assert_eq!(s.text, "customer orders");
+        assert!(s.filters.is_empty());
+
+        let s = search("customer platform:hive");
+        assert_eq!(s.text, "customer");
+        assert_eq!(groups("customer platform:hive"), [[HIVE]]);
+
+        assert_eq!(search("platform:hive").text, "*");
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_escapes()
+    {
+        assert_eq!(search(r#""sales report" q3"#).text, r#""sales report" q3"#);
+        assert_eq!(search(r#""say \"hi\"""#).text, r#""say "hi"""#);
+        assert_eq!(groups(r#"name:"my table""#), [["name=my table"]]);
+        assert_eq!(groups(r#"name:"a (b)""#), [["name=a (b)"]]);
+    }
+
+    #[test]
+    fn values_are_spelled_as_datahub_fields()
+    {
+        assert_eq!(groups("platform:hive"), [[HIVE]]);
+        assert_eq!(groups("platform:urn:li:dataPlatform:hive"), [[HIVE]]);
+        assert_eq!(groups("tag:pii"), [[PII]]);
+        assert_eq!(groups("origin:prod"), [["origin=PROD"]]);
+        assert_eq!(groups("type:VIEW"), [["typeNames=View"]]);
+        assert_eq!(groups("field:email"), [["fieldPaths=email"]]);
+    }
+
+    #[test]
+    fn and_is_implied_between_terms()
+    {
+        assert_eq!(groups("platform:hive AND tag:pii"), [[HIVE, PII]]);
+        assert_eq!(groups("platform:hive tag:pii"), [[HIVE, PII]]);
+    }
+
+    #[test]
+    fn or_makes_alternative_groups()
+    {
+        assert_eq!(groups("platform:hive OR platform:kafka"), [[HIVE], [KAFKA]]);
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or()
+    {
+        assert_eq!(groups("platform:hive OR platform:kafka tag:pii"), vec![vec![HIVE], vec![KAFKA, PII]]);
+    }
+
+    #[test]
+    fn not_and_minus_negate()
+    {
+        assert_eq!(groups("NOT tag:pii"), [["-tags=urn:li:tag:pii"]]);
+        assert_eq!(groups("-tag:pii"), [["-tags=urn:li:tag:pii"]]);
+        assert_eq!(groups("NOT NOT tag:pii"), [[PII]]);
+    }
+
+    #[test]
+    fn parentheses_nest()
+    {
+        assert_eq!(
+            groups("(platform:hive OR platform:kafka) AND (tag:pii OR tag:gdpr)"),
+            [[HIVE, PII], [HIVE, GDPR], [KAFKA, PII], [KAFKA, GDPR]]
+        );
+        assert_eq!(groups("((platform:hive))"), [[HIVE]]);
+    }
+
+    #[test]
+    fn not_is_pushed_down_through_groups()
+    {
+        assert_eq!(
+            groups("NOT (tag:pii OR tag:gdpr)"),
+            [["-tags=urn:li:tag:pii", "-tags=urn:li:tag:gdpr"]]
+        );
+        assert_eq!(
+            groups("-(tag:pii platform:hive)"),
+            [["-tags=urn:li:tag:pii"], ["-platform=urn:li:dataPlatform:hive"]]
+        );
+    }
+
+    #[test]
+    fn wildcards_start_or_end_names_and_fields()
+    {
+        assert_eq!(groups("name:cust*"), [["name^=cust"]]);
+        assert_eq!(groups("name:*_tmp"), [["name$=_tmp"]]);
+        assert_eq!(groups("name:*cust*"), [["name*=cust"]]);
+        assert_eq!(groups("field:*email"), [["fieldPaths$=email"]]);
+    }
+
+    #[test]
+    fn groups_are_limited()
+    {
+        let pairs = |n: usize| (0..n)
+            .map(|i| format!("(tag:a{i} OR tag:b{i})"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // 2^5 alternatives is the most allowed.
+        assert_eq!(search(&pairs(5)).filters.len(), MAX_GROUPS);
+
+        let query = pairs(6);
+        let at = query.find("tag:a5").expect("sixth pair");
+        assert_eq!(error(&query), (at, format!("expands to more than {MAX_GROUPS} alternatives")));
+
+        let query = (0..=MAX_GROUPS).map(|i| format!("tag:t{i}")).collect::<Vec<_>>().join(" OR ");
+        assert_eq!(error(&query), (0, format!("expands to more than {MAX_GROUPS} alternatives")));
+    }
+
+    #[test]
+    fn errors_point_at_the_mistake()
+    {
+        assert_eq!(error(""), (0, "expected a search term".into()));
+        assert_eq!(error("tag:pii OR"), (10, "expected a search term".into()));
+        assert_eq!(error("AND tag:pii"), (0, "expected a search term before AND".into()));
+        assert_eq!(error("tag:pii OR OR tag:gdpr"), (11, "expected a search term before OR".into()));
+        assert_eq!(error("tag:pii )"), (8, "unexpected ')'".into()));
+        assert_eq!(error("tag:gdpr (tag:pii"), (9, "unclosed '('".into()));
+        assert_eq!(error("platform: hive"), (9, "expected a value after 'platform:'".into()));
+        assert_eq!(error(r#"customer name:"open"#), (14, "unterminated quote".into()));
+        assert_eq!(
+            error("tag:pii owner:bob"),
+            (8, format!("unknown field 'owner', expected one of {FIELDS}"))
+        );
+        assert_eq!(error("tag:pi*"), (0, "wildcards only apply to name and field, not tag".into()));
+        assert_eq!(error("name:a*b"), (0, "wildcards may only start or end a value".into()));
+        assert_eq!(error("name:*"), (0, "wildcards may only start or end a value".into()));
+        assert_eq!(
+            error("tag:pii OR customer"),
+            (11, "free text can only be ANDed with the rest of the query".into())
+        );
+    }
+
+    #[test]
+    fn positions_count_characters_not_bytes()
+    {
+        assert_eq!(error("café tag:"), (9, "expected a value after 'tag:'".into()));
+        assert_eq!(error("日本 )").0, 3);
+    }
+}
@@ -0,0 +1,58 @@
+use axum::http::StatusCode;
+use once_cell::sync::Lazy;
+
+use crate::api::v1::graphql::{AddTagFactory, RemoveTagFactory};
+use crate::backend::Backend;
+use crate::cache::{Cache, Route};
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::schemas::DatasetAddTagResponse;
+
+
+static ADD_TAG: Lazy<AddTagFactory>       = Lazy::new(AddTagFactory::new);
+static REMOVE_TAG: Lazy<RemoveTagFactory> = Lazy::new(RemoveTagFactory::new);
+
+/// Tags an entity, then drops the cached responses of `routes` and any
+/// that mention the entity or the tag.
+pub async fn add(
+    id: &str,
+    tag: &str,
+    routes: &[Route],
+    backend: &Backend,
+    cache: &Cache,
+    ctx: &Context
+) -> Result<StatusCode, ApiError>
+{
+    let bytes = backend.add_tag(ctx, &ADD_TAG.body(id, tag)).await?;
+    succeeded(&bytes, || format!("unable to add {tag} to {id}"))?;
+    cache.invalidate(routes, &[id, tag]);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Untags an entity, then drops the cached responses like `add` does.
+pub async fn remove(
+    id: &str,
+    tag: &str,
+    routes: &[Route],
+    backend: &Backend,
+    cache: &Cache,
+    ctx: &Context
+) -> Result<StatusCode, ApiError>
+{
+    let bytes = backend.remove_tag(ctx, &REMOVE_TAG.body(id, tag)).await?;
+    succeeded(&bytes, || format!("unable to remove {tag} from {id}"))?;
+    cache.invalidate(routes, &[id, tag]);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DataHub answers tag mutations with whether they took.
+fn succeeded(bytes: &[u8], failure: impl FnOnce() -> String) -> Result<(), ApiError>
+{
+    let result: DatasetAddTagResponse = serde_json::from_slice(bytes)?;
+    match result.data {
+        Some(res) if res.success => Ok(()),
+        _ => Err(ApiError::Unprocessable(failure()))
+    }
+}
@@ -1,32 +1,39 @@
+use std::sync::Arc;
+
 use axum::{
-    Json, Router,
-    extract::{Extension, Path},
-    http::{Request, StatusCode},
-    response::IntoResponse,
-    routing::get,
+    extract::{Extension, OriginalUri},
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
-use hyper::{client::HttpConnector, Body};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 
 use crate::api::v1::{
+    extract::{Json, Path},
     graphql::{
         FilterFactory,
-        GetAllFactory,
         GetOneFactory,
-        QueryFactory,
-        NameFactory,
+        ListFactory,
     },
-    datasets::QUERY_VALUES as DATASET_VALUES,
-    params::{QueryParams, QueryType}
+    cursor,
+    datasets::query_values,
+    listing,
+    openapi::ApiRouter,
+    params::{Listed, Listing},
+    report,
 };
-use crate::datahub::{post, GRAPHQL_ENDPOINT, INGEST_ENDPOINT};
+use crate::backend::Backend;
+use crate::cache::{Cache, CacheStatus, Route};
+use crate::context::{Context, Mutation};
+use crate::error::{ApiError, Problem};
 use crate::schemas::{
     self,
     requests,
+    Collection,
     CreateTag,
     DeleteTag,
     Datasets,
+    Links,
     QueryResponse,
     Tags,
     TagEnvelope,
@@ -45,14 +52,8 @@ const QUERY_VALUES: &str = "
     }
 ";
 
-static GET_ALL: Lazy<GetAllFactory>     = Lazy::new(|| GetAllFactory::new("TAG", QUERY_VALUES));
+static LIST: Lazy<ListFactory>          = Lazy::new(|| ListFactory::new("TAG", QUERY_VALUES));
 static GET_BY_ID: Lazy<GetOneFactory>   = Lazy::new(|| GetOneFactory::new("tag", QUERY_VALUES));
-static GET_BY_NAME: Lazy<NameFactory>   = Lazy::new(|| NameFactory::new("TAG", QUERY_VALUES));
-static GET_BY_QUERY: Lazy<QueryFactory> = Lazy::new(|| QueryFactory::new("TAG", QUERY_VALUES));
-static DATASETS_BY_TAG: Lazy<FilterFactory>
-    = Lazy::new(|| FilterFactory::new("DATASETS", DATASET_VALUES, "tags"));
-
-type Client = hyper::client::Client<HttpConnector, Body>;
 
 #[derive(Deserialize)]
 struct TagResponse<'a> {
@@ -60,114 +61,184 @@ struct TagResponse<'a> {
     data: schemas::datahub::TagEntity<'a>,
 }
 
-pub fn routes() -> Router
+pub fn routes(api: ApiRouter) -> ApiRouter
 {
-    Router::new()
-        .route("/",
-            get(by_query)
-                .post(create_tag)
-        )
-        .route("/:id", 
-            get(by_id)
-                .delete(delete_tag)
-        )
-        .route("/:id/datasets", get(datasets_by_tag))
+    api
+        .route::<__path_by_query, _, _>(by_query)
+        .route::<__path_create_tag, _, _>(create_tag)
+        .route::<__path_by_id, _, _>(by_id)
+        .route::<__path_delete_tag, _, _>(delete_tag)
+        .route::<__path_datasets_by_tag, _, _>(datasets_by_tag)
 }
 
+#[utoipa::path(
+    get,
+    path = "/tags/{id}",
+    tag = "tags",
+    summary = "Tag with the specified id",
+    params(("id" = String, Path, description = "Tag urn")),
+    responses(
+        (status = 200, description = "The tag", body = TagEnvelope),
+        (status = 404, description = "No such tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_id(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-) -> Json<TagEnvelope>
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Json<TagEnvelope>), ApiError>
 {
     let body = GET_BY_ID.body(&id);
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: TagResponse = serde_json::from_slice(&bytes).unwrap();
-
-    TagEnvelope::from(&body.data).into()
+    let (status, bytes) = cache.fetch(Route::Tag, &ctx, &body, || backend.get(&ctx, &body)).await?;
+    let body: TagResponse = serde_json::from_slice(&bytes)?;
+
+    match body.data.entity {
+        Some(..) => Ok((status, TagEnvelope::from(&body.data).into())),
+        None => Err(ApiError::NotFound(id))
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    summary = "All tags, or those matching a query or name",
+    params(
+        ("query" = Option<String>, Query, description = "Tags with any value like this"),
+        ("name" = Option<String>, Query, description = "Tags with names like this, up to limit (not paged)"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of tags, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of tags to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("facets" = Option<String>, Query, description = "Comma separated facets to count matches by: platform, tags, origin, subtype (not with name)"),
+        ("sort" = Option<String>, Query, description = "name, or -name for descending order (default relevance)"),
+    ),
+    responses(
+        (status = 200, description = "A page of tags", body = Tags, headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown, conflicting or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn by_query(
-    Extension(client): Extension<Client>,
-    req: Request<Body>
-) -> Json<schemas::Tags>
+    Listed(params, ..): Listed<Tags>,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Json<schemas::Tags>), ApiError>
 {
-    let params = QueryParams::from(&req);
-    let body = match params.query {
-        QueryType::All          => GET_ALL.body(&params),
-        QueryType::Name(name)   => GET_BY_NAME.body(name, &params),
-        QueryType::Query(query) => GET_BY_QUERY.body(query, &params),
-        QueryType::Tags(..) => panic!("?tags not supported")
-    };
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: QueryResponse = serde_json::from_slice(&bytes).unwrap();
-
-    Tags::from(&body).into()
+    let (status, bytes) = listing::fetch(Route::Tags, &LIST, &params, &backend, &cache, &ctx).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let (links, tags) = listing::page::<Tags>(&uri, &params, &body);
+
+    Ok((status, links, tags.into()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/tags/{id}/datasets",
+    tag = "tags",
+    summary = "Datasets with the specified tag",
+    params(
+        ("id" = String, Path, description = "Tag urn"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of datasets, 1 to 1000 (default 10)"),
+        ("offset" = Option<i32>, Query, description = "Number of datasets to skip (default 0); offset plus limit may not exceed 10000, use cursor beyond"),
+        ("cursor" = Option<String>, Query, description = "Where to carry on from, taken from a previous page's next link; not with offset"),
+        ("sort" = Option<String>, Query, description = "name, lastModified or platform, with a leading - for descending order (default relevance)"),
+        ("fields" = Option<String>, Query, description = "Comma separated fields to return (id always is), default all"),
+        ("include" = Option<String>, Query, description = "Comma separated aspects to fetch: schema, tags, platform or none (default all)"),
+        ("format" = Option<String>, Query, description = "json (default), csv or parquet, for this page; overrides the Accept header"),
+        ("report" = Option<String>, Query, description = "With csv or parquet, a row per dataset (datasets, the default) or per schema field (fields)"),
+    ),
+    responses(
+        (status = 200, description = "A page of datasets, as JSON or a report", content(
+            (Datasets = "application/json"),
+            (String = "text/csv"),
+            (Vec<u8> = "application/vnd.apache.parquet"),
+        ), headers(("Link" = String, description = "RFC 8288 next and prev page links"))),
+        (status = 400, description = "Unknown or out of range query parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The Accept header names no format the page can be sent as", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn datasets_by_tag(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-    req: Request<Body>
-) -> Json<schemas::Datasets>
+    Listing { page, projection, output }: Listing,
+    OriginalUri(uri): OriginalUri,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    ctx: Context
+) -> Result<(CacheStatus, Links, Response), ApiError>
 {
-    let params = QueryParams::from(&req);
-    let body = DATASETS_BY_TAG.body(&id, &params);
-    let resp = post(&client, GRAPHQL_ENDPOINT, body)
-        .await
-        .unwrap();
-    let bytes = hyper::body::to_bytes(resp.into_body())
-        .await
-        .unwrap();
-    let body: QueryResponse = serde_json::from_slice(&bytes).unwrap();
-
-    Datasets::from(&body).into()
+    let factory = FilterFactory::new("DATASET", &query_values(&projection), "tags");
+    let body = factory.body(&id, &page);
+    let (status, bytes) = cache.fetch(Route::TagDatasets, &ctx, &body, || cursor::search(&backend, &ctx, &page, &body)).await?;
+    let body: QueryResponse = serde_json::from_slice(&bytes)?;
+    let links = cursor::links(&uri, &page, &body);
+
+    let datasets = Datasets::from(&body).link(&links).project(projection);
+
+    Ok((status, links, report::render(&output, datasets, projection)?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/tags",
+    tag = "tags",
+    summary = "Create a tag",
+    request_body = requests::CreateTag,
+    responses(
+        (status = 201, description = "The new tag", body = schemas::Tag),
+        (status = 400, description = "The body isn't a valid request", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn create_tag(
-    Extension(client): Extension<Client>,
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation,
     Json(payload): Json<requests::CreateTag>
-) -> impl IntoResponse
+) -> Result<impl IntoResponse, ApiError>
 {
     let name = payload.name;
     let urn  = format!("urn:li:tag:{name}");
-    let desc = payload.description.as_ref().map_or_else(|| "", |s| s);
+    let desc = payload.description.as_deref().unwrap_or("");
     let body = CreateTag::new(&urn, &name, desc);
-    let resp = post(&client, INGEST_ENDPOINT, body)
-        .await
-        .unwrap();
+    backend.ingest(&ctx, body.to_string()).await?;
+    cache.invalidate(&[Route::Tags], &[&urn]);
 
-    let status = if resp.status() == StatusCode::OK {
-        StatusCode::CREATED
-    } else {
-        StatusCode::INTERNAL_SERVER_ERROR
-    };
-
-    (status, Json(schemas::Tag::new(urn, Some(name), Some(desc.to_string()))))
+    Ok((StatusCode::CREATED, Json(schemas::Tag::new(urn, Some(name), Some(desc.to_string())))))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    tag = "tags",
+    summary = "Delete the specified tag",
+    params(("id" = String, Path, description = "Tag urn")),
+    responses(
+        (status = 204, description = "The tag was deleted"),
+        (status = 401, description = "The caller must be identified", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 async fn delete_tag(
     Path(id): Path<String>,
-    Extension(client): Extension<Client>,
-) -> StatusCode
+    Extension(backend): Extension<Backend>,
+    Extension(cache): Extension<Arc<Cache>>,
+    Mutation(ctx): Mutation
+) -> Result<StatusCode, ApiError>
 {
     let body = DeleteTag::new(&id);
-    let resp = post(&client, INGEST_ENDPOINT, body)
-        .await
-        .unwrap();
-
-    if resp.status() == StatusCode::OK {
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::INTERNAL_SERVER_ERROR
-    }
+    backend.ingest(&ctx, body.to_string()).await?;
+
+    // Every dataset, chart, dashboard and pipeline, listed or on its own,
+    // may embed the deleted tag without its request mentioning it.
+    let routes = [
+        Route::Tags, Route::TagDatasets,
+        Route::Datasets, Route::Dataset, Route::DatasetJobs, Route::PlatformDatasets,
+        Route::Charts, Route::Chart, Route::ChartDatasets,
+        Route::Dashboards, Route::Dashboard,
+        Route::Pipelines, Route::Pipeline, Route::PipelineJobs,
+    ];
+    cache.invalidate(&routes, &[&id]);
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fs};
+
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+
+use crate::config::{AuthConfig, ConfigError};
+
+
+/// Resolves the identity of a caller from a verified JWT or an API key.
+pub struct Authenticator {
+    jwt: Option<(DecodingKey, Validation)>,
+    claim: String,
+    api_keys: HashMap<String, String>,
+    pub require_identity: bool,
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig) -> Result<Authenticator, ConfigError>
+    {
+        let key = match (&config.jwt_secret, &config.jwt_public_key) {
+            (Some(secret), None) => {
+                Some((DecodingKey::from_secret(secret.as_bytes()), Algorithm::HS256))
+            },
+            (None, Some(path)) => {
+                let pem = fs::read(path)
+                    .map_err(|e| ConfigError::Read(path.clone(), e))?;
+                let key = DecodingKey::from_rsa_pem(&pem)
+                    .map_err(|e| ConfigError::Invalid {
+                        key: "auth.jwt_public_key",
+                        value: path.display().to_string(),
+                        reason: e.to_string(),
+                    })?;
+                Some((key, Algorithm::RS256))
+            },
+            _ => None
+        };
+
+        let jwt = key.map(|(key, alg)| {
+            // `exp` is required unless the config explicitly says otherwise,
+            // and checked whenever it's present.
+            let mut validation = Validation::new(alg);
+            if config.jwt_allow_no_expiry {
+                validation.required_spec_claims.remove("exp");
+            }
+            if let Some(issuer) = &config.jwt_issuer {
+                validation.set_issuer(&[issuer]);
+            }
+            match &config.jwt_audience {
+                Some(audience) => validation.set_audience(&[audience]),
+                None => validation.validate_aud = false,
+            }
+            (key, validation)
+        });
+
+        Ok(Authenticator {
+            jwt,
+            claim: config.identity_claim.clone(),
+            api_keys: config.api_keys.clone(),
+            require_identity: config.require_identity,
+        })
+    }
+
+    /// The corpuser urn for a bearer token, when it is a JWT we can verify.
+    pub fn identify_token(&self, token: &str) -> Option<String>
+    {
+        let (key, validation) = self.jwt.as_ref()?;
+        let claims = decode::<HashMap<String, Value>>(token, key, validation).ok()?.claims;
+
+        claims.get(&self.claim)
+            .and_then(Value::as_str)
+            .map(actor)
+    }
+
+    /// The corpuser urn for an API key, or None when the key is unknown.
+    pub fn identify_api_key(&self, key: &str) -> Option<String>
+    {
+        self.api_keys.get(key).map(|id| actor(id))
+    }
+}
+
+fn actor(id: &str) -> String
+{
+    if id.starts_with("urn:li:corpuser:") {
+        id.to_owned()
+    } else {
+        format!("urn:li:corpuser:{id}")
+    }
+}
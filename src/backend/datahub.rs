@@ -0,0 +1,272 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use hyper::body::Bytes;
+use rand::Rng;
+use tokio::time::Instant;
+use tracing::Span;
+
+use crate::backend::MetadataBackend;
+use crate::breaker::{Breaker, State};
+use crate::config::{DataHubConfig, RetryConfig};
+use crate::context::Context;
+use crate::datahub;
+use crate::error::ApiError;
+use crate::health::{self, Check};
+use crate::metrics::METRICS;
+use crate::schemas::GraphQL;
+
+type Client = datahub::HyperClient;
+
+const PROBE: &str = r#"{"query":"{ __typename }"}"#;
+
+pub struct DataHubBackend {
+    client: Client,
+    config: DataHubConfig,
+    breaker: Breaker,
+}
+
+impl DataHubBackend {
+    pub fn new(client: Client, config: DataHubConfig) -> DataHubBackend
+    {
+        let breaker = Breaker::new(&config.breaker);
+        DataHubBackend { client, config, breaker }
+    }
+
+    async fn query(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.retried(body.operation(), || datahub::query(&self.client, &self.config, ctx, body)).await
+    }
+
+    // Queries are safe to repeat, so transient failures are retried.
+    async fn retried<F>(&self, operation: &str, send: impl Fn() -> F) -> Result<Bytes, ApiError>
+    where
+        F: Future<Output = Result<Bytes, ApiError>>
+    {
+        let mut attempt = 0;
+        loop {
+            let result = self.call(operation, send()).await;
+            match result {
+                Err(err) if err.is_transient() && attempt < self.config.retry.retries => {
+                    attempt += 1;
+                    METRICS.retried(operation);
+                    tokio::time::sleep(backoff(&self.config.retry, attempt)).await;
+                },
+                result => return result
+            }
+        }
+    }
+
+    // Mutations are sent once: a failed attempt may still have been applied.
+    async fn mutate(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.call(body.operation(), datahub::query(&self.client, &self.config, ctx, body)).await
+    }
+
+    async fn call(
+        &self,
+        operation: &str,
+        call: impl Future<Output = Result<Bytes, ApiError>>
+    ) -> Result<Bytes, ApiError>
+    {
+        if let Err(err) = self.breaker.admit() {
+            METRICS.rejected(operation, &err);
+            return Err(err)
+        }
+
+        let start = Instant::now();
+        let result = call.await;
+        self.breaker.record(&result);
+        observe(operation, start, &result);
+
+        result
+    }
+}
+
+#[async_trait]
+impl MetadataBackend for DataHubBackend {
+    async fn search(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.query(ctx, body).await
+    }
+
+    async fn autocomplete(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.query(ctx, body).await
+    }
+
+    async fn get(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.query(ctx, body).await
+    }
+
+    async fn add_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.mutate(ctx, body).await
+    }
+
+    async fn remove_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        self.mutate(ctx, body).await
+    }
+
+    async fn ingest(&self, ctx: &Context, body: String) -> Result<Bytes, ApiError>
+    {
+        self.call("ingest", datahub::ingest(&self.client, &self.config, ctx, body)).await
+    }
+
+    async fn probe(&self, timeout: Duration) -> Vec<Check>
+    {
+        let ctx = Context::default();
+        let (graphql, ingest) = tokio::join!(
+            health::check("graphql", timeout, datahub::query(&self.client, &self.config, &ctx, PROBE)),
+            health::check("ingest", timeout, datahub::probe(&self.client, &self.config, &self.config.ingest_endpoint)),
+        );
+
+        let breaker = match self.breaker.state() {
+            State::Open { until } => {
+                let wait = until.saturating_duration_since(Instant::now());
+                Check::down("breaker", format!("open after repeated failures, next attempt in {}s", wait.as_secs()))
+            },
+            _ => Check::up("breaker", Duration::ZERO),
+        };
+
+        vec![graphql, ingest, breaker]
+    }
+}
+
+// Exponential backoff with jitter: the second half of each delay is
+// random so retries from concurrent requests spread out.
+fn backoff(retry: &RetryConfig, attempt: u32) -> Duration
+{
+    let ceiling = retry.backoff_ms
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(retry.max_backoff_ms);
+    let half = ceiling / 2;
+
+    Duration::from_millis(half + rand::thread_rng().gen_range(0..=ceiling - half))
+}
+
+fn observe(operation: &str, start: Instant, result: &Result<Bytes, ApiError>)
+{
+    let elapsed = start.elapsed();
+    let span = Span::current();
+    span.record("operation", operation);
+    span.record("upstream_ms", elapsed.as_millis() as u64);
+
+    METRICS.upstream(operation, elapsed, result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{Router, http::{StatusCode, Uri}, routing::post};
+    use axum_server::Handle;
+
+    use super::*;
+    use crate::config::BreakerConfig;
+    use crate::datahub;
+    use crate::schemas::Variables;
+
+
+    // The paths DataHub was sent.
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    /// Serves a DataHub that is always unavailable on a free port, noting
+    /// each call it gets.
+    async fn unavailable(calls: Calls) -> String
+    {
+        let answer = |uri: Uri| async move {
+            calls.lock().expect("unpoisoned").push(uri.path().to_owned());
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        let app = Router::new()
+            .route("/api/graphql", post(answer.clone()))
+            .route("/ingest", post(answer));
+
+        let handle = Handle::new();
+        let server = axum_server::bind("127.0.0.1:0".parse().expect("address"))
+            .handle(handle.clone())
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+
+        let addr = handle.listening().await.expect("stand-in listening");
+        format!("http://{addr}")
+    }
+
+    fn backend(url: &str, retry: RetryConfig) -> DataHubBackend
+    {
+        let config = DataHubConfig {
+            graphql_endpoint: format!("{url}/api/graphql"),
+            ingest_endpoint: format!("{url}/ingest"),
+            retry,
+            breaker: BreakerConfig { threshold: 100, cooldown_ms: 1_000 },
+            ..DataHubConfig::default()
+        };
+        DataHubBackend::new(datahub::client(&config).expect("client"), config)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_jitter()
+    {
+        let retry = RetryConfig { retries: 5, backoff_ms: 100, max_backoff_ms: 1_000 };
+        for (attempt, ceiling) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1_000), (40, 1_000)] {
+            let delays: Vec<_> = (0..200).map(|_| backoff(&retry, attempt).as_millis()).collect();
+            assert!(delays.iter().all(|ms| (ceiling / 2..=ceiling).contains(ms)), "attempt {attempt}: {delays:?}");
+            assert!(delays.iter().any(|ms| *ms != delays[0]), "attempt {attempt} isn't jittered");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failures_are_retried_after_a_backoff()
+    {
+        let backend = backend("http://127.0.0.1:9", RetryConfig { retries: 3, backoff_ms: 100, max_backoff_ms: 250 });
+        let sent = Mutex::new(Vec::new());
+        let send = || async {
+            sent.lock().expect("unpoisoned").push(Instant::now());
+            Err(ApiError::Upstream(StatusCode::SERVICE_UNAVAILABLE))
+        };
+
+        let result = backend.retried("tag", send).await;
+        assert!(matches!(result, Err(ApiError::Upstream(StatusCode::SERVICE_UNAVAILABLE))));
+        let sent = sent.into_inner().expect("unpoisoned");
+        let gaps: Vec<_> = sent.windows(2).map(|pair| (pair[1] - pair[0]).as_millis()).collect();
+        assert_eq!(gaps.len(), 3);
+        for (gap, ceiling) in gaps.iter().zip([100, 200, 250]) {
+            assert!((ceiling / 2..=ceiling).contains(gap), "{gap}ms after a {ceiling}ms ceiling");
+        }
+
+        // Failures that are the request's own come straight back.
+        let tries = Mutex::new(0);
+        let send = || async {
+            *tries.lock().expect("unpoisoned") += 1;
+            Err(ApiError::Upstream(StatusCode::BAD_REQUEST))
+        };
+        let start = Instant::now();
+        assert!(backend.retried("tag", send).await.is_err());
+        assert_eq!((tries.into_inner().expect("unpoisoned"), start.elapsed()), (1, Duration::ZERO));
+    }
+
+    // Sent over the wire, as the paused clock would time requests out.
+    #[tokio::test]
+    async fn mutations_are_never_retried()
+    {
+        let calls = Calls::default();
+        let url = unavailable(calls.clone()).await;
+        let backend = backend(&url, RetryConfig { retries: 3, backoff_ms: 1, max_backoff_ms: 2 });
+        let ctx = Context::default();
+        let query = GraphQL::new("query tag", Variables::Urn("urn:li:tag:pii"));
+        let mutation = GraphQL::new("mutation addTag", Variables::Urn("urn:li:tag:pii"));
+
+        assert!(backend.add_tag(&ctx, &mutation).await.is_err());
+        assert!(backend.remove_tag(&ctx, &mutation).await.is_err());
+        assert!(backend.ingest(&ctx, "{}".into()).await.is_err());
+        assert_eq!(*calls.lock().expect("unpoisoned"), ["/api/graphql", "/api/graphql", "/ingest"]);
+
+        calls.lock().expect("unpoisoned").clear();
+        assert!(backend.search(&ctx, &query).await.is_err());
+        assert_eq!(calls.lock().expect("unpoisoned").len(), 4);
+    }
+}
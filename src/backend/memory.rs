@@ -0,0 +1,437 @@
+use std::{fs, path::Path, sync::RwLock, time::Duration};
+
+use async_trait::async_trait;
+use hyper::body::Bytes;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::backend::MetadataBackend;
+use crate::config::ConfigError;
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::health::Check;
+use crate::schemas::{graphql::{AndFilter, SortInput}, Condition, GraphQL, Variables, SortOrder};
+
+
+/// A DataHub stand-in serving entities from a JSON fixture. Entities are
+/// stored in the shape the GraphQL selection sets produce (aliases and
+/// `__typename` included) and mutations only live as long as the process.
+pub struct MemoryBackend {
+    entities: RwLock<Vec<Value>>,
+}
+
+#[derive(Deserialize)]
+struct Fixture {
+    entities: Vec<Value>,
+}
+
+impl MemoryBackend {
+    pub fn from_file(path: &Path) -> Result<MemoryBackend, ConfigError>
+    {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Read(path.to_owned(), e))?;
+        let fixture: Fixture = serde_json::from_str(&text)
+            .map_err(|e| ConfigError::Fixture(path.to_owned(), e))?;
+
+        Ok(MemoryBackend::new(fixture.entities))
+    }
+
+    pub fn new(entities: Vec<Value>) -> MemoryBackend
+    {
+        MemoryBackend { entities: RwLock::new(entities) }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<Value>>
+    {
+        self.entities.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<Value>>
+    {
+        self.entities.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl MetadataBackend for MemoryBackend {
+    async fn search(&self, _ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        let entities = self.read();
+        match body.variables() {
+            Variables::SearchInput(input) => {
+                let matches = matching(&entities, input.class, &input.query, &input.filters, &input.sort);
+                let page = page(&matches, input.start, input.count);
+
+                respond(json!({
+                    "results": {
+                        "__typename": "SearchResults",
+                        "start": input.start,
+                        "count": page.len(),
+                        "total": matches.len(),
+                        "entities": page,
+                        "facets": facets(&matches),
+                    }
+                }))
+            },
+            // The scroll id is simply the offset the next page starts at.
+            Variables::ScrollInput(input) => {
+                let matches = matching(&entities, input.types[0], &input.query, &input.filters, &input.sort);
+                let start = input.scroll_id.as_deref()
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or(0);
+                let page = page(&matches, start, input.count);
+                let end = start as usize + page.len();
+                let next = (end < matches.len()).then(|| end.to_string());
+
+                respond(json!({
+                    "results": {
+                        "__typename": "ScrollResults",
+                        "next": next,
+                        "count": page.len(),
+                        "total": matches.len(),
+                        "entities": page,
+                        "facets": facets(&matches),
+                    }
+                }))
+            },
+            _ => Err(unsupported("search"))
+        }
+    }
+
+    async fn autocomplete(&self, _ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        let input = match body.variables() {
+            Variables::AutoCompleteInput(input) => input,
+            _ => return Err(unsupported("autocomplete"))
+        };
+
+        let query = input.query.to_lowercase();
+        let entities = self.read();
+        let matches: Vec<_> = entities.iter()
+            .filter(|e| is_class(e, input.class))
+            .filter(|e| name(e).to_lowercase().contains(&query))
+            .take(input.limit.max(0) as usize)
+            .collect();
+
+        respond(json!({
+            "results": {
+                "__typename": "AutoCompleteResults",
+                "entities": matches,
+            }
+        }))
+    }
+
+    async fn get(&self, _ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        let (urn, related) = match body.variables() {
+            Variables::Urn(urn) => (*urn, None),
+            Variables::Related(input) => (input.urn, Some((input.start, input.count))),
+            _ => return Err(unsupported("get"))
+        };
+
+        let entities = self.read();
+        let entity = entities.iter()
+            .find(|e| e["urn"] == urn)
+            .map(|e| resolved(e, &entities))
+            .map(|e| match related {
+                Some((start, count)) => paged_relationships(e, start, count),
+                None => e,
+            });
+
+        respond(json!({ "entity": entity }))
+    }
+
+    async fn add_tag(&self, _ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        let input = match body.variables() {
+            Variables::TagAssociationInput(input) => input,
+            _ => return Err(unsupported("add_tag"))
+        };
+
+        let mut entities = self.write();
+        let tag = entities.iter()
+            .find(|e| e["urn"] == input.tag)
+            .cloned()
+            .unwrap_or_else(|| json!({ "urn": input.tag }));
+        let success = match entities.iter_mut().find(|e| e["urn"] == input.resource) {
+            Some(entity) => {
+                let tags = &mut entity["tags"]["tags"];
+                if !tags.is_array() {
+                    *tags = json!([]);
+                }
+                if let Some(tags) = tags.as_array_mut() {
+                    if !tags.iter().any(|t| t["tag"]["urn"] == input.tag) {
+                        tags.push(json!({ "tag": tag }));
+                    }
+                }
+                true
+            },
+            None => false
+        };
+
+        respond(json!({ "success": success }))
+    }
+
+    async fn remove_tag(&self, _ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>
+    {
+        let input = match body.variables() {
+            Variables::TagAssociationInput(input) => input,
+            _ => return Err(unsupported("remove_tag"))
+        };
+
+        let mut entities = self.write();
+        let success = match entities.iter_mut().find(|e| e["urn"] == input.resource) {
+            Some(entity) => {
+                if let Some(tags) = entity["tags"]["tags"].as_array_mut() {
+                    tags.retain(|t| t["tag"]["urn"] != input.tag);
+                }
+                true
+            },
+            None => false
+        };
+
+        respond(json!({ "success": success }))
+    }
+
+    async fn ingest(&self, _ctx: &Context, body: String) -> Result<Bytes, ApiError>
+    {
+        let proposal: Value = serde_json::from_str(&body)?;
+        let snapshot = &proposal["entity"]["value"]["com.linkedin.metadata.snapshot.TagSnapshot"];
+        let urn = snapshot["urn"].as_str()
+            .ok_or_else(|| unsupported("ingest"))?;
+
+        let mut entities = self.write();
+        for aspect in snapshot["aspects"].as_array().into_iter().flatten() {
+            if let Some(props) = aspect.get("com.linkedin.tag.TagProperties") {
+                entities.retain(|e| e["urn"] != urn);
+                entities.push(json!({
+                    "__typename": "Tag",
+                    "urn": urn,
+                    "properties": props,
+                }));
+            } else if aspect["com.linkedin.common.Status"]["removed"] == true {
+                entities.retain(|e| e["urn"] != urn);
+            }
+        }
+
+        Ok(Bytes::from_static(b"{}"))
+    }
+
+    async fn probe(&self, _timeout: Duration) -> Vec<Check>
+    {
+        vec![Check::up("fixture", Duration::ZERO)]
+    }
+}
+
+fn respond(data: Value) -> Result<Bytes, ApiError>
+{
+    Ok(Bytes::from(serde_json::to_vec(&json!({ "data": data }))?))
+}
+
+fn unsupported(operation: &str) -> ApiError
+{
+    ApiError::BadRequest(format!("unsupported {operation} request"))
+}
+
+fn is_class(entity: &Value, class: &str) -> bool
+{
+    let typename = match class {
+        "TAG"           => "Tag",
+        "DATASET"       => "Dataset",
+        "DATA_PLATFORM" => "DataPlatform",
+        "CHART"         => "Chart",
+        "DASHBOARD"     => "Dashboard",
+        "DATA_FLOW"     => "DataFlow",
+        "DATA_JOB"      => "DataJob",
+        _ => return false
+    };
+
+    entity["__typename"] == typename
+}
+
+// Entities the fixture only gives the urn of, e.g. those related to
+// another entity, are filled in from the fixture, as DataHub resolves them.
+// Selection sets never ask for a related entity's own relationships, so
+// those are left out, which also stops a dataset and the jobs writing it
+// from filling each other in forever.
+fn resolved(value: &Value, entities: &[Value]) -> Value
+{
+    match value {
+        Value::Object(fields) if fields.len() == 1 && fields.contains_key("urn") => {
+            entities.iter()
+                .find(|e| e["urn"] == value["urn"])
+                .map_or_else(|| value.clone(), |entity| resolved(&unrelated(entity), entities))
+        },
+        Value::Object(fields) => Value::Object(fields.iter()
+            .map(|(key, value)| (key.clone(), resolved(value, entities)))
+            .collect()
+        ),
+        Value::Array(values) => Value::Array(values.iter()
+            .map(|value| resolved(value, entities))
+            .collect()
+        ),
+        value => value.clone()
+    }
+}
+
+fn unrelated(entity: &Value) -> Value
+{
+    match entity {
+        Value::Object(fields) => Value::Object(fields.iter()
+            .filter(|(_, value)| value.get("relationships").is_none())
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+        ),
+        entity => entity.clone()
+    }
+}
+
+// Every relationships block of the entity cut down to the requested page,
+// keeping its total.
+fn paged_relationships(mut entity: Value, start: i32, count: i32) -> Value
+{
+    if let Value::Object(fields) = &mut entity {
+        let blocks = fields.values_mut()
+            .filter_map(|block| block.get_mut("relationships"))
+            .filter_map(Value::as_array_mut);
+        for relationships in blocks {
+            relationships.drain(..(start.max(0) as usize).min(relationships.len()));
+            relationships.truncate(count.max(0) as usize);
+        }
+    }
+    entity
+}
+
+fn name(entity: &Value) -> &str
+{
+    entity["properties"]["name"].as_str()
+        .or_else(|| entity["name"].as_str())
+        .or_else(|| entity["urn"].as_str())
+        .unwrap_or_default()
+}
+
+fn matching<'a>(
+    entities: &'a [Value],
+    class: &str,
+    query: &str,
+    filters: &[AndFilter],
+    sort: &Option<SortInput>
+) -> Vec<&'a Value>
+{
+    let mut matches: Vec<_> = entities.iter()
+        .filter(|e| is_class(e, class))
+        .filter(|e| matches_search(e, query, filters))
+        .collect();
+    if let Some(sort) = sort {
+        let criterion = &sort.criterion;
+        matches.sort_by(|a, b| {
+            let order = sort_value(a, criterion.field).cmp(&sort_value(b, criterion.field));
+            if criterion.order == SortOrder::Descending { order.reverse() } else { order }
+        });
+    }
+
+    matches
+}
+
+fn page(matches: &[&Value], start: i32, count: i32) -> Vec<Value>
+{
+    matches.iter()
+        .skip(start.max(0) as usize)
+        .take(count.max(0) as usize)
+        .map(|e| json!({ "entity": e }))
+        .collect()
+}
+
+fn matches_search(entity: &Value, query: &str, filters: &[AndFilter]) -> bool
+{
+    let text = query.trim_matches('*').to_lowercase();
+    let matches_query = text.is_empty()
+        || name(entity).to_lowercase().contains(&text)
+        || entity["urn"].as_str().unwrap_or_default().to_lowercase().contains(&text);
+
+    // DataHub ORs the conjunctions together; none at all matches everything.
+    matches_query && (filters.is_empty() || filters.iter()
+        .any(|group| group.and.iter().all(|f| {
+            let found = field_values(entity, f.field).iter()
+                .any(|v| f.values.iter().any(|wanted| compare(f.condition, v, wanted)));
+            found != f.negated
+        })))
+}
+
+// Fixture entities carry no modification times, so sorting on them keeps
+// the fixture's order.
+fn sort_value(entity: &Value, field: &str) -> Option<String>
+{
+    match field {
+        "_entityName" => Some(name(entity).to_lowercase()),
+        field => field_values(entity, field).into_iter().next(),
+    }
+}
+
+fn compare(condition: Condition, value: &str, wanted: &str) -> bool
+{
+    match condition {
+        Condition::Equal     => value == wanted,
+        Condition::Contain   => value.contains(wanted),
+        Condition::StartWith => value.starts_with(wanted),
+        Condition::EndWith   => value.ends_with(wanted),
+    }
+}
+
+// Counts of each value of the fields DataHub aggregates on, across every
+// match rather than just the page.
+fn facets(matches: &[&Value]) -> Value
+{
+    let facets: Vec<_> = ["platform", "origin", "tags", "typeNames"].into_iter()
+        .map(|field| {
+            let mut counts: Vec<(String, usize)> = Vec::new();
+            for value in matches.iter().flat_map(|e| field_values(e, field)) {
+                match counts.iter_mut().find(|(v, _)| *v == value) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((value, 1)),
+                }
+            }
+            let aggregations: Vec<_> = counts.into_iter()
+                .map(|(value, count)| json!({ "value": value, "count": count }))
+                .collect();
+
+            json!({ "field": field, "aggregations": aggregations })
+        })
+        .collect();
+
+    Value::Array(facets)
+}
+
+fn field_values(entity: &Value, field: &str) -> Vec<String>
+{
+    match field {
+        "tags" => entity["tags"]["tags"].as_array().into_iter().flatten()
+            .filter_map(|t| t["tag"]["urn"].as_str())
+            .map(str::to_owned)
+            .collect(),
+        "platform" => {
+            let platform = &entity["platform"];
+            platform["urn"].as_str().map(str::to_owned)
+                .or_else(|| platform["name"].as_str().map(|n| format!("urn:li:dataPlatform:{n}")))
+                .into_iter()
+                .collect()
+        },
+        "typeNames" => entity["sub_types"]["names"].as_array().into_iter().flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        "name" => [&entity["name"], &entity["properties"]["name"]].into_iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        "fieldPaths" => entity["schema"]["fields"].as_array().into_iter().flatten()
+            .filter_map(|f| f["path"].as_str())
+            .map(str::to_owned)
+            .collect(),
+        "origin" => entity["properties"]["origin"].as_str()
+            .map(str::to_owned)
+            .into_iter()
+            .collect(),
+        _ => vec![]
+    }
+}
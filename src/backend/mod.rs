@@ -0,0 +1,33 @@
+mod datahub;
+mod memory;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use hyper::body::Bytes;
+
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::health::Check;
+use crate::schemas::GraphQL;
+
+pub use self::datahub::DataHubBackend;
+pub use self::memory::MemoryBackend;
+
+pub type Backend = Arc<dyn MetadataBackend>;
+
+/// The metadata operations the routes depend on. Every call answers with
+/// the raw JSON document DataHub itself would have returned for the request
+/// so handlers can deserialize it with the borrowed `schemas::datahub` types.
+#[async_trait]
+pub trait MetadataBackend: Send + Sync {
+    async fn search(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>;
+    async fn autocomplete(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>;
+    async fn get(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>;
+    async fn add_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>;
+    async fn remove_tag(&self, ctx: &Context, body: &GraphQL<'_>) -> Result<Bytes, ApiError>;
+    async fn ingest(&self, ctx: &Context, body: String) -> Result<Bytes, ApiError>;
+
+    /// Checks each dependency the backend relies on, giving each the timeout.
+    async fn probe(&self, timeout: Duration) -> Vec<Check>;
+}
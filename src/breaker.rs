@@ -0,0 +1,208 @@
+use std::{sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::config::BreakerConfig;
+use crate::error::ApiError;
+use crate::metrics::METRICS;
+
+
+/// Fails DataHub calls fast once `threshold` consecutive calls have failed.
+/// After `cooldown` a single trial call is let through; its outcome either
+/// closes the breaker again or restarts the cooldown. A trial that never
+/// reports back (the caller went away) is replaced after another cooldown.
+pub struct Breaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Clone, Copy)]
+pub enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+impl Breaker {
+    pub fn new(config: &BreakerConfig) -> Breaker
+    {
+        METRICS.breaker(&State::Closed { failures: 0 });
+
+        Breaker {
+            threshold: config.threshold,
+            cooldown: Duration::from_millis(config.cooldown_ms),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> State
+    {
+        *self.lock()
+    }
+
+    /// Lets a call through unless the breaker is open or its trial call is
+    /// still in flight.
+    pub fn admit(&self) -> Result<(), ApiError>
+    {
+        let mut state = self.lock();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(ApiError::Unavailable(until - now))
+                }
+                self.set(&mut state, State::HalfOpen { since: now });
+                Ok(())
+            },
+            State::HalfOpen { since } => {
+                let now = Instant::now();
+                if now < since + self.cooldown {
+                    return Err(ApiError::Unavailable(since + self.cooldown - now))
+                }
+                self.set(&mut state, State::HalfOpen { since: now });
+                Ok(())
+            },
+        }
+    }
+
+    pub fn record<T>(&self, result: &Result<T, ApiError>)
+    {
+        let failed = matches!(result, Err(err) if err.is_transient());
+        let mut state = self.lock();
+        let next = match (*state, failed) {
+            (_, false) => State::Closed { failures: 0 },
+            (State::Closed { failures }, true) if failures + 1 < self.threshold => {
+                State::Closed { failures: failures + 1 }
+            },
+            _ => State::Open { until: Instant::now() + self.cooldown },
+        };
+        self.set(&mut state, next);
+    }
+
+    fn set(&self, state: &mut State, next: State)
+    {
+        if let (State::Closed { .. }, State::Open { .. }) = (*state, next) {
+            tracing::warn!(cooldown_ms = self.cooldown.as_millis() as u64, "DataHub circuit breaker opened");
+        }
+        *state = next;
+        METRICS.breaker(state);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State>
+    {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+
+
+    const COOLDOWN: Duration = Duration::from_millis(1_000);
+
+    fn breaker() -> Breaker
+    {
+        Breaker::new(&BreakerConfig { threshold: 3, cooldown_ms: COOLDOWN.as_millis() as u64 })
+    }
+
+    fn fail(breaker: &Breaker)
+    {
+        breaker.record::<()>(&Err(ApiError::Upstream(StatusCode::SERVICE_UNAVAILABLE)));
+    }
+
+    fn succeed(breaker: &Breaker)
+    {
+        breaker.record(&Ok(()));
+    }
+
+    // How long an open breaker says to wait, if it refuses the call.
+    fn refused(breaker: &Breaker) -> Option<Duration>
+    {
+        match breaker.admit() {
+            Err(ApiError::Unavailable(wait)) => Some(wait),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(()) => None,
+        }
+    }
+
+    fn name(state: State) -> &'static str
+    {
+        match state {
+            State::Closed { .. }   => "closed",
+            State::Open { .. }     => "open",
+            State::HalfOpen { .. } => "half-open",
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_consecutive_failures_and_closes_after_a_trial()
+    {
+        let breaker = breaker();
+        fail(&breaker);
+        fail(&breaker);
+        succeed(&breaker);
+        fail(&breaker);
+        fail(&breaker);
+        assert_eq!(name(breaker.state()), "closed");
+        assert_eq!(refused(&breaker), None);
+
+        fail(&breaker);
+        assert_eq!(name(breaker.state()), "open");
+        assert_eq!(refused(&breaker), Some(COOLDOWN));
+        tokio::time::advance(COOLDOWN / 4).await;
+        assert_eq!(refused(&breaker), Some(COOLDOWN * 3 / 4));
+
+        tokio::time::advance(COOLDOWN * 3 / 4).await;
+        assert_eq!(refused(&breaker), None);
+        assert_eq!(name(breaker.state()), "half-open");
+        assert_eq!(refused(&breaker), Some(COOLDOWN), "one trial at a time");
+
+        succeed(&breaker);
+        assert_eq!(name(breaker.state()), "closed");
+        assert_eq!(refused(&breaker), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_or_abandoned_trials_reopen()
+    {
+        let breaker = breaker();
+        (0..3).for_each(|_| fail(&breaker));
+        tokio::time::advance(COOLDOWN).await;
+        assert_eq!(refused(&breaker), None);
+
+        fail(&breaker);
+        assert_eq!(name(breaker.state()), "open");
+        assert_eq!(refused(&breaker), Some(COOLDOWN));
+
+        // A trial whose caller went away is replaced after another cooldown.
+        tokio::time::advance(COOLDOWN).await;
+        assert_eq!(refused(&breaker), None);
+        tokio::time::advance(COOLDOWN / 2).await;
+        assert_eq!(refused(&breaker), Some(COOLDOWN / 2));
+        tokio::time::advance(COOLDOWN / 2).await;
+        assert_eq!(refused(&breaker), None);
+        assert_eq!(name(breaker.state()), "half-open");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn only_datahub_failures_count()
+    {
+        let breaker = breaker();
+        for _ in 0..5 {
+            breaker.record::<()>(&Err(ApiError::NotFound("urn:li:tag:x".into())));
+            breaker.record::<()>(&Err(ApiError::Upstream(StatusCode::BAD_REQUEST)));
+        }
+        assert_eq!(name(breaker.state()), "closed");
+
+        breaker.record::<()>(&Err(ApiError::Timeout(COOLDOWN)));
+        breaker.record::<()>(&Err(ApiError::Upstream(StatusCode::BAD_GATEWAY)));
+        fail(&breaker);
+        assert_eq!(name(breaker.state()), "open");
+    }
+}
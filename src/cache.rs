@@ -0,0 +1,416 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::Duration,
+};
+
+use axum::{
+    http::HeaderValue,
+    response::{IntoResponseParts, ResponseParts},
+};
+use hyper::body::Bytes;
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use crate::config::{CacheConfig, ConfigError};
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::schemas::GraphQL;
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Route {
+    Tags,
+    Tag,
+    TagDatasets,
+    Datasets,
+    Dataset,
+    DatasetJobs,
+    Platforms,
+    Platform,
+    PlatformDatasets,
+    Charts,
+    Chart,
+    ChartDatasets,
+    Dashboards,
+    Dashboard,
+    Pipelines,
+    Pipeline,
+    PipelineJobs,
+}
+
+pub enum CacheStatus {
+    Hit,
+    Miss,
+}
+
+impl CacheStatus {
+    /// A response built from several fetches is only a hit if they all were.
+    pub fn and(self, other: CacheStatus) -> CacheStatus
+    {
+        match (self, other) {
+            (CacheStatus::Hit, CacheStatus::Hit) => CacheStatus::Hit,
+            _ => CacheStatus::Miss
+        }
+    }
+}
+
+/// Upstream responses for read routes keyed on the caller and the serialized
+/// GraphQL body, since DataHub answers with each caller's own privileges.
+/// Entries expire after the TTL of the route that stored them and the least
+/// recently used ones are dropped once the cache grows past `max_bytes`.
+/// A response fetched while entries were being invalidated isn't stored,
+/// as it may predate the change that invalidated them.
+pub struct Cache {
+    enabled: bool,
+    max_bytes: usize,
+    ttls: HashMap<Route, Duration>,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    size: usize,
+    generation: u64,
+    entries: LruCache<String, Entry>,
+}
+
+struct Entry {
+    route: Route,
+    bytes: Bytes,
+    expires: Instant,
+}
+
+impl Route {
+    const ALL: [Route; 17] = [
+        Route::Tags, Route::Tag, Route::TagDatasets,
+        Route::Datasets, Route::Dataset, Route::DatasetJobs,
+        Route::Platforms, Route::Platform, Route::PlatformDatasets,
+        Route::Charts, Route::Chart, Route::ChartDatasets,
+        Route::Dashboards, Route::Dashboard,
+        Route::Pipelines, Route::Pipeline, Route::PipelineJobs,
+    ];
+
+    pub fn path(&self) -> &'static str
+    {
+        match self {
+            Route::Tags             => "/tags",
+            Route::Tag              => "/tags/:id",
+            Route::TagDatasets      => "/tags/:id/datasets",
+            Route::Datasets         => "/datasets",
+            Route::Dataset          => "/datasets/:id",
+            Route::DatasetJobs      => "/datasets/:id/jobs",
+            Route::Platforms        => "/platforms",
+            Route::Platform         => "/platforms/:id",
+            Route::PlatformDatasets => "/platforms/:id/datasets",
+            Route::Charts           => "/charts",
+            Route::Chart            => "/charts/:id",
+            Route::ChartDatasets    => "/charts/:id/datasets",
+            Route::Dashboards       => "/dashboards",
+            Route::Dashboard        => "/dashboards/:id",
+            Route::Pipelines        => "/pipelines",
+            Route::Pipeline         => "/pipelines/:id",
+            Route::PipelineJobs     => "/pipelines/:id/jobs",
+        }
+    }
+}
+
+impl Cache {
+    pub fn new(config: &CacheConfig) -> Result<Cache, ConfigError>
+    {
+        let mut ttls = HashMap::new();
+        for (path, secs) in &config.ttl {
+            let route = Route::ALL.iter()
+                .find(|r| r.path() == path)
+                .ok_or_else(|| ConfigError::Invalid {
+                    key: "cache.ttl",
+                    value: path.clone(),
+                    reason: "not a cacheable route".into(),
+                })?;
+            ttls.insert(*route, Duration::from_secs(*secs));
+        }
+        for route in Route::ALL {
+            ttls.entry(route).or_insert(Duration::from_secs(config.default_ttl));
+        }
+
+        Ok(Cache {
+            enabled: config.enabled,
+            max_bytes: config.max_bytes,
+            ttls,
+            inner: Mutex::new(Inner { size: 0, generation: 0, entries: LruCache::unbounded() }),
+        })
+    }
+
+    /// Answers from the cache when it holds a live entry for the caller and
+    /// body, otherwise runs the upstream call and stores its result.
+    pub async fn fetch<F, Fut>(
+        &self,
+        route: Route,
+        ctx: &Context,
+        body: &GraphQL<'_>,
+        upstream: F
+    ) -> Result<(CacheStatus, Bytes), ApiError>
+        where F: FnOnce() -> Fut, Fut: Future<Output = Result<Bytes, ApiError>>
+    {
+        let ttl = self.ttls.get(&route).copied().unwrap_or_default();
+        if !self.enabled || ttl.is_zero() {
+            return Ok((CacheStatus::Miss, upstream().await?))
+        }
+
+        let key = format!("{} {body}", principal(ctx));
+        let generation = match self.get(&key) {
+            Ok(bytes) => return Ok((CacheStatus::Hit, bytes)),
+            Err(generation) => generation
+        };
+
+        let bytes = upstream().await?;
+        self.put(key, Entry { route, bytes: bytes.clone(), expires: Instant::now() + ttl }, generation);
+
+        Ok((CacheStatus::Miss, bytes))
+    }
+
+    /// Drops every entry stored by one of the routes, plus any entry whose
+    /// request mentions one of the urns.
+    pub fn invalidate(&self, routes: &[Route], urns: &[&str])
+    {
+        if !self.enabled {
+            return
+        }
+
+        let mut inner = self.lock();
+        inner.generation += 1;
+        let stale: Vec<_> = inner.entries.iter()
+            .filter(|(key, entry)| {
+                routes.contains(&entry.route) || urns.iter().any(|urn| mentions(key, urn))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale {
+            inner.remove(&key);
+        }
+    }
+
+    // The live entry for the key, or the generation a fetch for it starts in.
+    fn get(&self, key: &str) -> Result<Bytes, u64>
+    {
+        let mut inner = self.lock();
+        match inner.entries.get(key) {
+            Some(entry) if entry.expires > Instant::now() => Ok(entry.bytes.clone()),
+            Some(..) => {
+                inner.remove(key);
+                Err(inner.generation)
+            },
+            None => Err(inner.generation)
+        }
+    }
+
+    fn put(&self, key: String, entry: Entry, generation: u64)
+    {
+        let size = key.len() + entry.bytes.len();
+        if size > self.max_bytes {
+            return
+        }
+
+        let mut inner = self.lock();
+        if inner.generation != generation {
+            return
+        }
+        inner.remove(&key);
+        inner.size += size;
+        inner.entries.put(key, entry);
+        while inner.size > self.max_bytes {
+            match inner.entries.pop_lru() {
+                Some((key, entry)) => inner.size -= key.len() + entry.bytes.len(),
+                None => break
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner>
+    {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, key: &str)
+    {
+        if let Some(entry) = self.entries.pop(key) {
+            self.size -= key.len() + entry.bytes.len();
+        }
+    }
+}
+
+// Who the response was fetched for: a digest of the forwarded token, so the
+// token itself isn't held in memory, and the actor.
+fn principal(ctx: &Context) -> String
+{
+    let token = ctx.token.as_ref()
+        .map_or_else(|| "-".to_owned(), |token| hex(&Sha256::digest(token.as_bytes())));
+    let actor = ctx.actor.as_deref().unwrap_or("-");
+
+    format!("{token} {actor}")
+}
+
+fn hex(bytes: &[u8]) -> String
+{
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Bodies are JSON, so urns appear as complete string values.
+fn mentions(key: &str, urn: &str) -> bool
+{
+    key.contains(&format!("\"{urn}\""))
+}
+
+impl IntoResponseParts for CacheStatus {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error>
+    {
+        let value = match self {
+            CacheStatus::Hit  => "HIT",
+            CacheStatus::Miss => "MISS",
+        };
+        res.headers_mut().insert("X-Cache", HeaderValue::from_static(value));
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::schemas::Variables;
+
+
+    fn sized(max_bytes: usize) -> Cache
+    {
+        let config = CacheConfig { max_bytes, ..CacheConfig::default() };
+        Cache::new(&config).expect("valid cache config")
+    }
+
+    fn caller(token: &str) -> Context
+    {
+        Context { token: Some(token.into()), ..Context::default() }
+    }
+
+    // Fetches the urn, answering upstream with `bytes` and counting the calls.
+    async fn fetch(cache: &Cache, ctx: &Context, urn: &str, calls: &AtomicUsize) -> CacheStatus
+    {
+        let body = GraphQL::new("query by_id", Variables::Urn(urn));
+        let (status, _) = cache.fetch(Route::Dataset, ctx, &body, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Bytes::from_static(&[b'x'; 100]))
+        }).await.expect("upstream answers");
+
+        status
+    }
+
+    fn hit(status: CacheStatus) -> bool
+    {
+        matches!(status, CacheStatus::Hit)
+    }
+
+    #[tokio::test]
+    async fn misses_then_hits()
+    {
+        let (cache, calls) = (sized(1 << 20), AtomicUsize::new(0));
+        assert!(!hit(fetch(&cache, &Context::default(), "urn:li:dataset:a", &calls).await));
+        assert!(hit(fetch(&cache, &Context::default(), "urn:li:dataset:a", &calls).await));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn callers_are_cached_apart()
+    {
+        let (cache, calls) = (sized(1 << 20), AtomicUsize::new(0));
+        let actor = Context { actor: Some("urn:li:corpuser:etl".into()), ..Context::default() };
+        for ctx in [caller("alice"), caller("bob"), actor, Context::default()] {
+            assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+            assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn entries_expire_after_their_ttl()
+    {
+        let (cache, calls) = (sized(1 << 20), AtomicUsize::new(0));
+        let ctx = Context::default();
+        fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await;
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entries_make_room()
+    {
+        let ctx = Context::default();
+        let entry = {
+            let cache = sized(1 << 20);
+            fetch(&cache, &ctx, "urn:li:dataset:a", &AtomicUsize::new(0)).await;
+            let size = cache.lock().size;
+            size
+        };
+
+        // Room for two entries, not three.
+        let (cache, calls) = (sized(2 * entry + entry / 2), AtomicUsize::new(0));
+        fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await;
+        fetch(&cache, &ctx, "urn:li:dataset:b", &calls).await;
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+
+        fetch(&cache, &ctx, "urn:li:dataset:c", &calls).await;
+        assert_eq!(cache.lock().size, 2 * entry);
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:c", &calls).await));
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:b", &calls).await));
+
+        let (cache, calls) = (sized(entry - 1), AtomicUsize::new(0));
+        fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await;
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        assert_eq!(cache.lock().size, 0);
+    }
+
+    #[tokio::test]
+    async fn invalidation_drops_routes_and_mentions()
+    {
+        let (cache, calls) = (sized(1 << 20), AtomicUsize::new(0));
+        let ctx = Context::default();
+        fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await;
+        fetch(&cache, &ctx, "urn:li:dataset:b", &calls).await;
+
+        cache.invalidate(&[], &["urn:li:dataset:a"]);
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:b", &calls).await));
+
+        cache.invalidate(&[Route::Dataset], &[]);
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:b", &calls).await));
+    }
+
+    #[tokio::test]
+    async fn fetches_overtaken_by_invalidation_are_not_stored()
+    {
+        let cache = sized(1 << 20);
+        let ctx = Context::default();
+        let body = GraphQL::new("query by_id", Variables::Urn("urn:li:dataset:a"));
+
+        let (status, _) = cache.fetch(Route::Dataset, &ctx, &body, || async {
+            cache.invalidate(&[Route::Tags], &["urn:li:tag:pii"]);
+            Ok(Bytes::from_static(b"before the tag was removed"))
+        }).await.expect("upstream answers");
+        assert!(!hit(status));
+
+        let calls = AtomicUsize::new(0);
+        assert!(!hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+        assert!(hit(fetch(&cache, &ctx, "urn:li:dataset:a", &calls).await));
+    }
+}
@@ -0,0 +1,798 @@
+use std::{collections::HashMap, fmt, fs, io, net::SocketAddr, path::{Path, PathBuf}};
+
+use axum::http::Uri;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+
+
+const DEFAULT_CONFIG: &str = "datasvc.toml";
+
+#[derive(Parser)]
+#[command(version, about = "Restful frontend to DataHub")]
+struct Args {
+    /// Path to a TOML configuration file
+    #[arg(long, env = "DATASVC_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Address the service listens on
+    #[arg(long, env = "DATASVC_BIND")]
+    bind: Option<String>,
+
+    /// DataHub GraphQL endpoint
+    #[arg(long, env = "DATASVC_GRAPHQL_ENDPOINT")]
+    graphql_endpoint: Option<String>,
+
+    /// DataHub ingest endpoint
+    #[arg(long, env = "DATASVC_INGEST_ENDPOINT")]
+    ingest_endpoint: Option<String>,
+
+    /// Actor urn sent to DataHub in the X-DataHub-Actor header
+    #[arg(long, env = "DATASVC_ACTOR")]
+    actor: Option<String>,
+
+    /// Service token sent to DataHub when the caller supplies none
+    #[arg(long, env = "DATASVC_DATAHUB_TOKEN", hide_env_values = true)]
+    datahub_token: Option<String>,
+
+    /// Shared secret verifying HS256 caller JWTs
+    #[arg(long, env = "DATASVC_JWT_SECRET", hide_env_values = true)]
+    jwt_secret: Option<String>,
+
+    /// Reject mutations from callers without a verified identity
+    #[arg(long, env = "DATASVC_REQUIRE_IDENTITY")]
+    require_identity: Option<bool>,
+
+    /// Log output format
+    #[arg(long, env = "DATASVC_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Metadata backend serving the routes
+    #[arg(long, env = "DATASVC_BACKEND")]
+    backend: Option<BackendKind>,
+
+    /// JSON fixture seeding the in-memory backend
+    #[arg(long, env = "DATASVC_FIXTURE")]
+    fixture: Option<PathBuf>,
+
+    /// Certificate chain served by the listener
+    #[arg(long, env = "DATASVC_TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+
+    /// Private key of the listener certificate
+    #[arg(long, env = "DATASVC_TLS_KEY")]
+    tls_key: Option<PathBuf>,
+
+    /// CA bundle verifying client certificates
+    #[arg(long, env = "DATASVC_TLS_CLIENT_CA")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Seconds between checks for renewed listener certificates
+    #[arg(long, env = "DATASVC_TLS_RELOAD_INTERVAL")]
+    tls_reload_interval: Option<u64>,
+
+    /// Milliseconds allowed to connect to DataHub
+    #[arg(long, env = "DATASVC_DATAHUB_CONNECT_TIMEOUT_MS")]
+    datahub_connect_timeout_ms: Option<u64>,
+
+    /// Milliseconds allowed for a DataHub request
+    #[arg(long, env = "DATASVC_DATAHUB_TIMEOUT_MS")]
+    datahub_timeout_ms: Option<u64>,
+
+    /// Retries of a failed DataHub query
+    #[arg(long, env = "DATASVC_DATAHUB_RETRIES")]
+    datahub_retries: Option<u32>,
+
+    /// Initial backoff between retries in milliseconds
+    #[arg(long, env = "DATASVC_DATAHUB_BACKOFF_MS")]
+    datahub_backoff_ms: Option<u64>,
+
+    /// Longest backoff between retries in milliseconds
+    #[arg(long, env = "DATASVC_DATAHUB_MAX_BACKOFF_MS")]
+    datahub_max_backoff_ms: Option<u64>,
+
+    /// Consecutive DataHub failures that open the circuit breaker
+    #[arg(long, env = "DATASVC_DATAHUB_BREAKER_THRESHOLD")]
+    datahub_breaker_threshold: Option<u32>,
+
+    /// Milliseconds the breaker stays open before a trial request
+    #[arg(long, env = "DATASVC_DATAHUB_BREAKER_COOLDOWN_MS")]
+    datahub_breaker_cooldown_ms: Option<u64>,
+
+    /// Trust the system root certificates for DataHub
+    #[arg(long, env = "DATASVC_DATAHUB_NATIVE_ROOTS")]
+    datahub_native_roots: Option<bool>,
+
+    /// CA bundle verifying DataHub's certificate
+    #[arg(long, env = "DATASVC_DATAHUB_CA_BUNDLE")]
+    datahub_ca_bundle: Option<PathBuf>,
+
+    /// Client certificate presented to DataHub
+    #[arg(long, env = "DATASVC_DATAHUB_CLIENT_CERT")]
+    datahub_client_cert: Option<PathBuf>,
+
+    /// Private key of the DataHub client certificate
+    #[arg(long, env = "DATASVC_DATAHUB_CLIENT_KEY")]
+    datahub_client_key: Option<PathBuf>,
+
+    /// Name verified against DataHub's certificate
+    #[arg(long, env = "DATASVC_DATAHUB_SERVER_NAME")]
+    datahub_server_name: Option<String>,
+
+    /// Cache responses from DataHub
+    #[arg(long, env = "DATASVC_CACHE_ENABLED")]
+    cache_enabled: Option<bool>,
+
+    /// Bytes of responses the cache holds
+    #[arg(long, env = "DATASVC_CACHE_MAX_BYTES")]
+    cache_max_bytes: Option<usize>,
+
+    /// Seconds a cached response lives without a per-route ttl
+    #[arg(long, env = "DATASVC_CACHE_DEFAULT_TTL")]
+    cache_default_ttl: Option<u64>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub datahub: DataHubConfig,
+    pub auth: AuthConfig,
+    pub cache: CacheConfig,
+    pub log: LogConfig,
+    pub health: HealthConfig,
+    pub backend: BackendConfig,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub tls: Option<ListenerTlsConfig>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerTlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub client_ca: Option<PathBuf>,
+
+    #[serde(default = "ListenerTlsConfig::reload_interval")]
+    pub reload_interval: u64,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataHubConfig {
+    pub graphql_endpoint: String,
+    pub ingest_endpoint: String,
+    pub actor: String,
+    pub token: Option<String>,
+    pub connect_timeout_ms: u64,
+    pub timeout_ms: u64,
+    pub retry: RetryConfig,
+    pub breaker: BreakerConfig,
+    pub tls: UpstreamTlsConfig,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpstreamTlsConfig {
+    pub native_roots: bool,
+    pub ca_bundle: Option<PathBuf>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    pub server_name: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub retries: u32,
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BreakerConfig {
+    pub threshold: u32,
+    pub cooldown_ms: u64,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: Option<String>,
+    pub jwt_public_key: Option<PathBuf>,
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+    /// Accept tokens without an `exp` claim, e.g. DataHub tokens that never expire.
+    pub jwt_allow_no_expiry: bool,
+    pub identity_claim: String,
+    pub api_keys: HashMap<String, String>,
+    pub require_identity: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub max_bytes: usize,
+    pub default_ttl: u64,
+    pub ttl: HashMap<String, u64>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: String,
+    pub format: LogFormat,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    pub window: u64,
+    pub timeout: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    pub kind: BackendKind,
+    pub fixture: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    #[value(name = "datahub")]
+    DataHub,
+    Memory,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Fixture(PathBuf, serde_json::Error),
+    Invalid { key: &'static str, value: String, reason: String },
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self
+    {
+        ServerConfig { bind: "127.0.0.1:3000".into(), tls: None }
+    }
+}
+
+impl ListenerTlsConfig {
+    fn reload_interval() -> u64
+    {
+        30
+    }
+}
+
+impl Default for DataHubConfig {
+    fn default() -> Self
+    {
+        DataHubConfig {
+            graphql_endpoint: "http://localhost:8080/api/graphql".into(),
+            ingest_endpoint: "http://localhost:8080/entities?action=ingest".into(),
+            actor: "urn:li:corpuser:datahub".into(),
+            token: None,
+            connect_timeout_ms: 2_000,
+            timeout_ms: 10_000,
+            retry: RetryConfig::default(),
+            breaker: BreakerConfig::default(),
+            tls: UpstreamTlsConfig::default(),
+        }
+    }
+}
+
+impl Default for UpstreamTlsConfig {
+    fn default() -> Self
+    {
+        UpstreamTlsConfig {
+            native_roots: true,
+            ca_bundle: None,
+            client_cert: None,
+            client_key: None,
+            server_name: None,
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self
+    {
+        RetryConfig { retries: 2, backoff_ms: 100, max_backoff_ms: 2_000 }
+    }
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self
+    {
+        BreakerConfig { threshold: 5, cooldown_ms: 30_000 }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self
+    {
+        AuthConfig {
+            jwt_secret: None,
+            jwt_public_key: None,
+            jwt_issuer: None,
+            jwt_audience: None,
+            jwt_allow_no_expiry: false,
+            identity_claim: "sub".into(),
+            api_keys: HashMap::new(),
+            require_identity: false,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self
+    {
+        CacheConfig {
+            enabled: true,
+            max_bytes: 64 * 1024 * 1024,
+            default_ttl: 30,
+            ttl: HashMap::new(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self
+    {
+        LogConfig { level: "info".into(), format: LogFormat::Text }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self
+    {
+        HealthConfig { window: 5, timeout: 2 }
+    }
+}
+
+impl Args {
+    /// Overrides the settings given as flags or DATASVC_* variables.
+    fn apply(self, config: &mut Config) -> Result<(), ConfigError>
+    {
+        fn set<T>(value: Option<T>, setting: &mut T)
+        {
+            if let Some(value) = value {
+                *setting = value;
+            }
+        }
+        fn set_some<T>(value: Option<T>, setting: &mut Option<T>)
+        {
+            if value.is_some() {
+                *setting = value;
+            }
+        }
+
+        self.listener_tls(&mut config.server.tls)?;
+        set(self.bind, &mut config.server.bind);
+
+        let datahub = &mut config.datahub;
+        set(self.graphql_endpoint, &mut datahub.graphql_endpoint);
+        set(self.ingest_endpoint, &mut datahub.ingest_endpoint);
+        set(self.actor, &mut datahub.actor);
+        set_some(self.datahub_token, &mut datahub.token);
+        set(self.datahub_connect_timeout_ms, &mut datahub.connect_timeout_ms);
+        set(self.datahub_timeout_ms, &mut datahub.timeout_ms);
+        set(self.datahub_retries, &mut datahub.retry.retries);
+        set(self.datahub_backoff_ms, &mut datahub.retry.backoff_ms);
+        set(self.datahub_max_backoff_ms, &mut datahub.retry.max_backoff_ms);
+        set(self.datahub_breaker_threshold, &mut datahub.breaker.threshold);
+        set(self.datahub_breaker_cooldown_ms, &mut datahub.breaker.cooldown_ms);
+        set(self.datahub_native_roots, &mut datahub.tls.native_roots);
+        set_some(self.datahub_ca_bundle, &mut datahub.tls.ca_bundle);
+        set_some(self.datahub_client_cert, &mut datahub.tls.client_cert);
+        set_some(self.datahub_client_key, &mut datahub.tls.client_key);
+        set_some(self.datahub_server_name, &mut datahub.tls.server_name);
+
+        set_some(self.jwt_secret, &mut config.auth.jwt_secret);
+        set(self.require_identity, &mut config.auth.require_identity);
+
+        set(self.cache_enabled, &mut config.cache.enabled);
+        set(self.cache_max_bytes, &mut config.cache.max_bytes);
+        set(self.cache_default_ttl, &mut config.cache.default_ttl);
+
+        set(self.log_format, &mut config.log.format);
+        set(self.backend, &mut config.backend.kind);
+        set_some(self.fixture, &mut config.backend.fixture);
+
+        Ok(())
+    }
+
+    // The listener needs both a certificate and its key, so flags can only
+    // stand up TLS together, though either updates a configured listener.
+    fn listener_tls(&self, tls: &mut Option<ListenerTlsConfig>) -> Result<(), ConfigError>
+    {
+        let tls = match (tls, &self.tls_cert, &self.tls_key) {
+            (Some(tls), ..) => tls,
+            (tls @ None, Some(cert), Some(key)) => tls.insert(ListenerTlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+                client_ca: None,
+                reload_interval: ListenerTlsConfig::reload_interval(),
+            }),
+            (None, None, None) if self.tls_client_ca.is_none() && self.tls_reload_interval.is_none() => {
+                return Ok(())
+            },
+            (None, cert, _) => return Err(ConfigError::Invalid {
+                key: "server.tls",
+                value: cert.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+                reason: "set both --tls-cert and --tls-key to serve TLS".into(),
+            }),
+        };
+
+        if let Some(cert) = &self.tls_cert {
+            tls.cert = cert.clone();
+        }
+        if let Some(key) = &self.tls_key {
+            tls.key = key.clone();
+        }
+        if self.tls_client_ca.is_some() {
+            tls.client_ca = self.tls_client_ca.clone();
+        }
+        if let Some(interval) = self.tls_reload_interval {
+            tls.reload_interval = interval;
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Builds the configuration from, in increasing precedence, the built-in
+    /// defaults, the TOML file, DATASVC_* environment variables and flags.
+    pub fn load() -> Result<Config, ConfigError>
+    {
+        let args = Args::parse();
+        let mut config = match &args.config {
+            Some(path) => Config::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG).exists() => {
+                Config::from_file(Path::new(DEFAULT_CONFIG))?
+            },
+            None => Config::default(),
+        };
+
+        args.apply(&mut config)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Config, ConfigError>
+    {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Read(path.to_owned(), e))?;
+
+        toml::from_str(&text)
+            .map_err(|e| ConfigError::Parse(path.to_owned(), e))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError>
+    {
+        self.bind_addr()?;
+        validate_endpoint("datahub.graphql_endpoint", &self.datahub.graphql_endpoint)?;
+        validate_endpoint("datahub.ingest_endpoint", &self.datahub.ingest_endpoint)?;
+
+        let actor = &self.datahub.actor;
+        match actor.strip_prefix("urn:li:corpuser:") {
+            Some(id) if !id.is_empty() => {},
+            _ => return Err(ConfigError::Invalid {
+                key: "datahub.actor",
+                value: actor.clone(),
+                reason: "expected a urn of the form urn:li:corpuser:<id>".into(),
+            })
+        }
+
+        let datahub = &self.datahub;
+        for (key, value) in [
+            ("datahub.connect_timeout_ms", datahub.connect_timeout_ms),
+            ("datahub.timeout_ms", datahub.timeout_ms),
+            ("datahub.breaker.threshold", datahub.breaker.threshold.into()),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::Invalid { key, value: "0".into(), reason: "must be positive".into() })
+            }
+        }
+
+        let tls = &datahub.tls;
+        if tls.client_cert.is_some() != tls.client_key.is_some() {
+            return Err(ConfigError::Invalid {
+                key: "datahub.tls.client_cert",
+                value: tls.client_cert.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+                reason: "set both datahub.tls.client_cert and datahub.tls.client_key, or neither".into(),
+            })
+        }
+        if let Some(name) = &tls.server_name {
+            if let Err(err) = rustls::ServerName::try_from(name.as_str()) {
+                return Err(ConfigError::Invalid {
+                    key: "datahub.tls.server_name",
+                    value: name.clone(),
+                    reason: err.to_string(),
+                })
+            }
+        }
+        if !tls.native_roots && tls.ca_bundle.is_none() {
+            return Err(ConfigError::Invalid {
+                key: "datahub.tls.native_roots",
+                value: "false".into(),
+                reason: "without the system roots a datahub.tls.ca_bundle is required".into(),
+            })
+        }
+
+        if let Err(err) = tracing_subscriber::EnvFilter::try_new(&self.log.level) {
+            return Err(ConfigError::Invalid {
+                key: "log.level",
+                value: self.log.level.clone(),
+                reason: err.to_string(),
+            })
+        }
+
+        let auth = &self.auth;
+        if auth.jwt_secret.is_some() && auth.jwt_public_key.is_some() {
+            return Err(ConfigError::Invalid {
+                key: "auth.jwt_public_key",
+                value: auth.jwt_public_key.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+                reason: "set either auth.jwt_secret or auth.jwt_public_key, not both".into(),
+            })
+        }
+        let identifiable = auth.jwt_secret.is_some()
+            || auth.jwt_public_key.is_some()
+            || !auth.api_keys.is_empty();
+        if auth.require_identity && !identifiable {
+            return Err(ConfigError::Invalid {
+                key: "auth.require_identity",
+                value: "true".into(),
+                reason: "requires a JWT key or at least one API key to identify callers".into(),
+            })
+        }
+
+        if self.health.timeout == 0 {
+            return Err(ConfigError::Invalid {
+                key: "health.timeout",
+                value: "0".into(),
+                reason: "probes need at least a second to answer".into(),
+            })
+        }
+
+        if self.backend.kind == BackendKind::Memory && self.backend.fixture.is_none() {
+            return Err(ConfigError::Invalid {
+                key: "backend.fixture",
+                value: String::new(),
+                reason: "the memory backend requires a fixture file".into(),
+            })
+        }
+
+        Ok(())
+    }
+
+    pub fn bind_addr(&self) -> Result<SocketAddr, ConfigError>
+    {
+        self.server.bind.parse()
+            .map_err(|e: std::net::AddrParseError| ConfigError::Invalid {
+                key: "server.bind",
+                value: self.server.bind.clone(),
+                reason: e.to_string(),
+            })
+    }
+}
+
+fn validate_endpoint(key: &'static str, value: &str) -> Result<(), ConfigError>
+{
+    let invalid = |reason: String| ConfigError::Invalid { key, value: value.into(), reason };
+
+    let uri: Uri = value.parse().map_err(|e: axum::http::uri::InvalidUri| invalid(e.to_string()))?;
+    match uri.scheme_str() {
+        Some("http") | Some("https") => {},
+        _ => return Err(invalid("expected an http:// or https:// url".into()))
+    }
+    if uri.host().is_none() {
+        return Err(invalid("missing host".into()))
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path, err)  => write!(f, "unable to read {}: {err}", path.display()),
+            Self::Parse(path, err) => write!(f, "invalid configuration in {}: {err}", path.display()),
+            Self::Fixture(path, err) => write!(f, "invalid fixture in {}: {err}", path.display()),
+            Self::Invalid { key, value, reason } => write!(f, "invalid {key} \"{value}\": {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn args(flags: &[&str]) -> Args
+    {
+        Args::try_parse_from(std::iter::once("rs-datasvc").chain(flags.iter().copied()))
+            .expect("valid flags")
+    }
+
+    fn layered(toml: &str, flags: &[&str]) -> Config
+    {
+        let mut config: Config = toml::from_str(toml).expect("valid toml");
+        args(flags).apply(&mut config).expect("applicable flags");
+        config
+    }
+
+    fn rejected(config: &Config) -> &'static str
+    {
+        match config.validate() {
+            Err(ConfigError::Invalid { key, .. }) => key,
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(()) => panic!("accepted an invalid configuration"),
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_overrides()
+    {
+        let config = layered("", &[]);
+        assert_eq!(config.datahub.timeout_ms, 10_000);
+        assert_eq!(config.datahub.retry.retries, 2);
+        assert_eq!(config.datahub.breaker.threshold, 5);
+        assert!(config.cache.enabled);
+        assert!(config.server.tls.is_none());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn flags_override_the_file()
+    {
+        let toml = r#"
+            [datahub]
+            timeout_ms = 5000
+            connect_timeout_ms = 500
+
+            [datahub.retry]
+            retries = 4
+            backoff_ms = 50
+
+            [datahub.breaker]
+            threshold = 3
+
+            [datahub.tls]
+            server_name = "datahub.internal"
+
+            [cache]
+            max_bytes = 1024
+        "#;
+
+        let config = layered(toml, &[]);
+        assert_eq!(config.datahub.timeout_ms, 5_000);
+        assert_eq!(config.datahub.retry.retries, 4);
+        assert_eq!(config.datahub.retry.max_backoff_ms, 2_000);
+        assert_eq!(config.cache.max_bytes, 1024);
+
+        let config = layered(toml, &[
+            "--datahub-timeout-ms", "7000",
+            "--datahub-retries", "0",
+            "--datahub-breaker-threshold", "9",
+            "--datahub-server-name", "datahub.example.com",
+            "--cache-enabled", "false",
+            "--cache-default-ttl", "5",
+        ]);
+        assert_eq!(config.datahub.timeout_ms, 7_000);
+        assert_eq!(config.datahub.connect_timeout_ms, 500);
+        assert_eq!(config.datahub.retry.retries, 0);
+        assert_eq!(config.datahub.retry.backoff_ms, 50);
+        assert_eq!(config.datahub.breaker.threshold, 9);
+        assert_eq!(config.datahub.tls.server_name.as_deref(), Some("datahub.example.com"));
+        assert!(!config.cache.enabled);
+        assert_eq!(config.cache.default_ttl, 5);
+        assert_eq!(config.cache.max_bytes, 1024);
+    }
+
+    #[test]
+    fn environment_overrides_the_file_and_flags_the_environment()
+    {
+        let toml = "[datahub.breaker]\ncooldown_ms = 1000\n";
+        assert_eq!(layered(toml, &[]).datahub.breaker.cooldown_ms, 1_000);
+
+        std::env::set_var("DATASVC_DATAHUB_BREAKER_COOLDOWN_MS", "2000");
+        let from_env = layered(toml, &[]);
+        let from_flag = layered(toml, &["--datahub-breaker-cooldown-ms", "3000"]);
+        std::env::remove_var("DATASVC_DATAHUB_BREAKER_COOLDOWN_MS");
+
+        assert_eq!(from_env.datahub.breaker.cooldown_ms, 2_000);
+        assert_eq!(from_flag.datahub.breaker.cooldown_ms, 3_000);
+    }
+
+    #[test]
+    fn listener_tls_flags_need_a_cert_and_key()
+    {
+        let config = layered("", &["--tls-cert", "cert.pem", "--tls-key", "key.pem"]);
+        let tls = config.server.tls.expect("tls listener");
+        assert_eq!(tls.cert, Path::new("cert.pem"));
+        assert_eq!(tls.reload_interval, 30);
+
+        let toml = "[server.tls]\ncert = \"a.pem\"\nkey = \"a.key\"\n";
+        let config = layered(toml, &["--tls-cert", "b.pem", "--tls-reload-interval", "5"]);
+        let tls = config.server.tls.expect("tls listener");
+        assert_eq!((tls.cert.as_path(), tls.key.as_path()), (Path::new("b.pem"), Path::new("a.key")));
+        assert_eq!(tls.reload_interval, 5);
+
+        let mut config = Config::default();
+        match args(&["--tls-cert", "cert.pem"]).apply(&mut config) {
+            Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, "server.tls"),
+            _ => panic!("served TLS without a key"),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_zero_timeouts()
+    {
+        let mut config = Config::default();
+        config.datahub.connect_timeout_ms = 0;
+        assert_eq!(rejected(&config), "datahub.connect_timeout_ms");
+
+        let mut config = Config::default();
+        config.datahub.timeout_ms = 0;
+        assert_eq!(rejected(&config), "datahub.timeout_ms");
+
+        let mut config = Config::default();
+        config.health.timeout = 0;
+        assert_eq!(rejected(&config), "health.timeout");
+    }
+
+    #[test]
+    fn validate_rejects_a_client_cert_without_its_key()
+    {
+        let mut config = Config::default();
+        config.datahub.tls.client_cert = Some("client.pem".into());
+        assert_eq!(rejected(&config), "datahub.tls.client_cert");
+
+        let mut config = Config::default();
+        config.datahub.tls.client_key = Some("client.key".into());
+        assert_eq!(rejected(&config), "datahub.tls.client_cert");
+    }
+
+    #[test]
+    fn validate_rejects_both_jwt_keys()
+    {
+        let mut config = Config::default();
+        config.auth.jwt_secret = Some("secret".into());
+        config.auth.jwt_public_key = Some("jwt.pem".into());
+        assert_eq!(rejected(&config), "auth.jwt_public_key");
+    }
+
+    #[test]
+    fn validate_rejects_required_identity_without_keys()
+    {
+        let mut config = Config::default();
+        config.auth.require_identity = true;
+        assert_eq!(rejected(&config), "auth.require_identity");
+
+        config.auth.api_keys.insert("key".into(), "urn:li:corpuser:etl".into());
+        assert!(config.validate().is_ok());
+    }
+}
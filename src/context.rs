@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequest, RequestParts},
+    http::header,
+};
+
+use crate::auth::Authenticator;
+use crate::error::ApiError;
+use crate::telemetry::RequestId;
+
+
+const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Per-request state forwarded with every upstream call.
+#[derive(Clone, Default)]
+pub struct Context {
+    pub token: Option<String>,
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// A Context for routes that change metadata. Rejects the request when
+/// callers must be identified and this one isn't.
+pub struct Mutation(pub Context);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Context {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let token = match req.headers().get(header::AUTHORIZATION) {
+            Some(value) => Some(bearer(value.to_str().ok())?),
+            None => None
+        };
+
+        let auth = req.extensions().get::<Arc<Authenticator>>();
+        let actor = match (auth, req.headers().get(API_KEY_HEADER)) {
+            (Some(auth), Some(key)) => {
+                let actor = key.to_str().ok().and_then(|key| auth.identify_api_key(key));
+                Some(actor.ok_or_else(|| ApiError::Unauthorized("unknown API key".into()))?)
+            },
+            (Some(auth), None) => token.as_deref().and_then(|t| auth.identify_token(t)),
+            (None, _) => None
+        };
+
+        let request_id = req.extensions().get::<RequestId>().map(|id| id.0.clone());
+
+        Ok(Context { token, actor, request_id })
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Mutation {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection>
+    {
+        let ctx = Context::from_request(req).await?;
+        let required = req.extensions().get::<Arc<Authenticator>>()
+            .is_some_and(|auth| auth.require_identity);
+
+        if required && ctx.actor.is_none() {
+            Err(ApiError::Unauthorized("changes require an authenticated caller".into()))
+        } else {
+            Ok(Mutation(ctx))
+        }
+    }
+}
+
+fn bearer(value: Option<&str>) -> Result<String, ApiError>
+{
+    value
+        .and_then(|v| v.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .ok_or_else(|| ApiError::Unauthorized("expected an Authorization: Bearer <token> header".into()))
+}
@@ -1,23 +1,312 @@
-use axum::http::{header, Method, Request};
+use std::time::Duration;
+
+use axum::http::{header, Method, Request, StatusCode};
 use hyper::{
     Body,
-    client::{HttpConnector, ResponseFuture},
+    body::Bytes,
+    client::HttpConnector,
 };
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 
-type HyperClient = hyper::client::Client<HttpConnector, Body>;
+use crate::config::{ConfigError, DataHubConfig};
+use crate::context::Context;
+use crate::error::ApiError;
+use crate::schemas::ErrorResponse;
+use crate::telemetry::REQUEST_ID_HEADER;
+use crate::tls;
 
-pub const INGEST_ENDPOINT: &str = "http://localhost:8080/entities?action=ingest";
-pub const GRAPHQL_ENDPOINT: &str = "http://localhost:8080/api/graphql";
+pub type HyperClient = hyper::client::Client<HttpsConnector<HttpConnector>, Body>;
 
-pub fn post(client: &HyperClient, url: &str, data: impl std::fmt::Display) -> ResponseFuture
+/// A client for the DataHub endpoints, speaking TLS to https:// ones.
+pub fn client(config: &DataHubConfig) -> Result<HyperClient, ConfigError>
 {
-    let req = Request::builder()
+    let mut http = HttpConnector::new();
+    http.set_connect_timeout(Some(Duration::from_millis(config.connect_timeout_ms)));
+    http.enforce_http(false);
+
+    let builder = HttpsConnectorBuilder::new()
+        .with_tls_config(tls::client_config(&config.tls)?)
+        .https_or_http();
+    let builder = match &config.tls.server_name {
+        Some(name) => builder.with_server_name(name.clone()),
+        None => builder,
+    };
+
+    Ok(hyper::client::Client::builder().build(builder.enable_http1().wrap_connector(http)))
+}
+
+pub async fn post(
+    client: &HyperClient,
+    config: &DataHubConfig,
+    ctx: &Context,
+    url: &str,
+    data: impl std::fmt::Display
+) -> Result<Bytes, ApiError>
+{
+    let mut req = Request::builder()
         .method(Method::POST)
         .uri(url)
-        .header("X-DataHub-Actor", "urn:li:corpuser:datahub")
-        .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(data.to_string()))
-        .unwrap();
+        .header("X-DataHub-Actor", ctx.actor.as_ref().unwrap_or(&config.actor))
+        .header(header::CONTENT_TYPE, "application/json");
+
+    // The caller's own token wins over the service token so DataHub
+    // applies the caller's privileges.
+    if let Some(token) = ctx.token.as_ref().or(config.token.as_ref()) {
+        req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    if let Some(id) = &ctx.request_id {
+        req = req.header(REQUEST_ID_HEADER, id);
+    }
+
+    let req = req.body(Body::from(data.to_string()))?;
+    let (status, bytes) = within(config, async {
+        let resp = client.request(req).await?;
+        let status = resp.status();
+        Ok((status, hyper::body::to_bytes(resp.into_body()).await?))
+    }).await?;
+
+    match status {
+        s if s.is_success() => Ok(bytes),
+        StatusCode::UNAUTHORIZED => Err(ApiError::Unauthorized("DataHub rejected the credentials".into())),
+        _ => Err(ApiError::Upstream(status))
+    }
+}
+
+pub async fn query(
+    client: &HyperClient,
+    config: &DataHubConfig,
+    ctx: &Context,
+    data: impl std::fmt::Display
+) -> Result<Bytes, ApiError>
+{
+    let bytes = post(client, config, ctx, &config.graphql_endpoint, data).await?;
+    let resp: ErrorResponse = serde_json::from_slice(&bytes)?;
+
+    match resp.errors {
+        Some(errors) if !errors.is_empty() => Err(ApiError::from(errors.as_slice())),
+        _ => Ok(bytes)
+    }
+}
+
+pub async fn ingest(
+    client: &HyperClient,
+    config: &DataHubConfig,
+    ctx: &Context,
+    data: impl std::fmt::Display
+) -> Result<Bytes, ApiError>
+{
+    post(client, config, ctx, &config.ingest_endpoint, data).await
+}
+
+/// Checks the endpoint answers. The ingest endpoint only accepts POSTs, so
+/// anything short of a server error or rejected credentials will do.
+pub async fn probe(
+    client: &HyperClient,
+    config: &DataHubConfig,
+    url: &str
+) -> Result<(), ApiError>
+{
+    let mut req = Request::builder()
+        .method(Method::GET)
+        .uri(url);
+    if let Some(token) = &config.token {
+        req = req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+
+    let req = req.body(Body::empty())?;
+    let resp = within(config, async { Ok(client.request(req).await?) }).await?;
+    match resp.status() {
+        s if s.is_server_error() => Err(ApiError::Upstream(s)),
+        StatusCode::UNAUTHORIZED => Err(ApiError::Unauthorized("DataHub rejected the credentials".into())),
+        _ => Ok(())
+    }
+}
+
+// Bounds the whole exchange, connecting included, by the configured timeout.
+async fn within<T>(
+    config: &DataHubConfig,
+    exchange: impl std::future::Future<Output = Result<T, ApiError>>
+) -> Result<T, ApiError>
+{
+    let limit = Duration::from_millis(config.timeout_ms);
+    tokio::time::timeout(limit, exchange).await
+        .unwrap_or(Err(ApiError::Timeout(limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::Arc};
+
+    use axum::{Router, routing::get};
+    use axum_server::{tls_rustls::RustlsConfig, Handle};
+    use rcgen::{
+        BasicConstraints,
+        Certificate,
+        CertificateParams,
+        ExtendedKeyUsagePurpose,
+        IsCa,
+    };
+
+    use super::*;
+    use crate::config::{ListenerTlsConfig, UpstreamTlsConfig};
+
+
+    // The name on the stand-in's certificate, which `localhost` doesn't match.
+    const SERVER_NAME: &str = "datahub.internal";
+
+    /// A self-signed CA with a server and a client certificate it issued,
+    /// written out as PEM files.
+    struct Pki {
+        dir: PathBuf,
+    }
+
+    impl Pki {
+        fn new(name: &str) -> Pki
+        {
+            let dir = std::env::temp_dir().join(format!("rs-datasvc-{name}-{}", std::process::id()));
+            fs::create_dir_all(&dir).expect("temp dir");
+
+            let mut params = CertificateParams::new(vec![]);
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = Certificate::from_params(params).expect("CA certificate");
+
+            let server = Certificate::from_params(CertificateParams::new(vec![SERVER_NAME.into()])).expect("server certificate");
+            let mut params = CertificateParams::new(vec![]);
+            params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+            let client = Certificate::from_params(params).expect("client certificate");
+
+            let pki = Pki { dir };
+            pki.write("ca.pem", ca.serialize_pem().expect("CA PEM"));
+            pki.write("server.pem", server.serialize_pem_with_signer(&ca).expect("server PEM"));
+            pki.write("server.key", server.serialize_private_key_pem());
+            pki.write("client.pem", client.serialize_pem_with_signer(&ca).expect("client PEM"));
+            pki.write("client.key", client.serialize_private_key_pem());
+            pki
+        }
+
+        fn write(&self, file: &str, pem: String)
+        {
+            fs::write(self.path(file), pem).expect("PEM written");
+        }
+
+        fn path(&self, file: &str) -> PathBuf
+        {
+            self.dir.join(file)
+        }
+    }
+
+    impl Drop for Pki {
+        fn drop(&mut self)
+        {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Serves HTTPS on a free port with the service's own listener
+    /// settings, returning its URL.
+    async fn stand_in(pki: &Pki, mtls: bool) -> String
+    {
+        let config = ListenerTlsConfig {
+            cert: pki.path("server.pem"),
+            key: pki.path("server.key"),
+            client_ca: mtls.then(|| pki.path("ca.pem")),
+            reload_interval: 0,
+        };
+        let rustls = RustlsConfig::from_config(Arc::new(tls::server_config(&config).expect("server TLS config")));
+        let app = Router::new().route("/", get(|| async { "ok" }));
+
+        let handle = Handle::new();
+        let server = axum_server::bind_rustls("127.0.0.1:0".parse().expect("address"), rustls)
+            .handle(handle.clone())
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+
+        let addr = handle.listening().await.expect("stand-in listening");
+        format!("https://localhost:{}/", addr.port())
+    }
+
+    fn upstream(tls: UpstreamTlsConfig) -> DataHubConfig
+    {
+        DataHubConfig { tls, ..DataHubConfig::default() }
+    }
+
+    async fn reaches(config: &DataHubConfig, url: &str) -> bool
+    {
+        let client = client(config).expect("client");
+        probe(&client, config, url).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn trusts_the_ca_bundle()
+    {
+        let pki = Pki::new("ca-bundle");
+        let url = stand_in(&pki, false).await;
+
+        let trusted = upstream(UpstreamTlsConfig {
+            native_roots: false,
+            ca_bundle: Some(pki.path("ca.pem")),
+            server_name: Some(SERVER_NAME.into()),
+            ..UpstreamTlsConfig::default()
+        });
+        assert!(reaches(&trusted, &url).await);
+
+        let untrusted = upstream(UpstreamTlsConfig {
+            native_roots: false,
+            ca_bundle: None,
+            server_name: Some(SERVER_NAME.into()),
+            ..UpstreamTlsConfig::default()
+        });
+        assert!(!reaches(&untrusted, &url).await);
+    }
+
+    #[tokio::test]
+    async fn checks_the_certificate_against_server_name()
+    {
+        let pki = Pki::new("server-name");
+        let url = stand_in(&pki, false).await;
+
+        // The URL's host isn't on the certificate, so only the override
+        // lets the handshake through.
+        let by_host = upstream(UpstreamTlsConfig {
+            native_roots: false,
+            ca_bundle: Some(pki.path("ca.pem")),
+            server_name: None,
+            ..UpstreamTlsConfig::default()
+        });
+        assert!(!reaches(&by_host, &url).await);
+
+        let other_name = upstream(UpstreamTlsConfig {
+            server_name: Some("elsewhere.internal".into()),
+            ..by_host.tls.clone()
+        });
+        assert!(!reaches(&other_name, &url).await);
+
+        let by_name = upstream(UpstreamTlsConfig {
+            server_name: Some(SERVER_NAME.into()),
+            ..by_host.tls.clone()
+        });
+        assert!(reaches(&by_name, &url).await);
+    }
+
+    #[tokio::test]
+    async fn presents_the_client_certificate()
+    {
+        let pki = Pki::new("mtls");
+        let url = stand_in(&pki, true).await;
+
+        let anonymous = upstream(UpstreamTlsConfig {
+            native_roots: false,
+            ca_bundle: Some(pki.path("ca.pem")),
+            server_name: Some(SERVER_NAME.into()),
+            ..UpstreamTlsConfig::default()
+        });
+        assert!(!reaches(&anonymous, &url).await);
 
-    client.request(req)
+        let identified = upstream(UpstreamTlsConfig {
+            client_cert: Some(pki.path("client.pem")),
+            client_key: Some(pki.path("client.key")),
+            ..anonymous.tls.clone()
+        });
+        assert!(reaches(&identified, &url).await);
+    }
 }
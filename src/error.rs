@@ -0,0 +1,251 @@
+use std::{fmt, time::Duration};
+
+use axum::{
+    Json,
+    extract::rejection::{JsonRejection, PathRejection},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::schemas::datahub::ErrorMessage;
+
+
+#[derive(Debug)]
+pub enum ApiError {
+    Request(axum::http::Error),
+    Connection(hyper::Error),
+    Upstream(StatusCode),
+    Timeout(Duration),
+    Unavailable(Duration),
+    GraphQL(StatusCode, Vec<String>),
+    Decode(serde_json::Error),
+    Encode(String),
+    NotFound(String),
+    BadRequest(String),
+    InvalidParams(Vec<InvalidParam>),
+    NotAcceptable(String),
+    Unauthorized(String),
+    Unprocessable(String),
+}
+
+/// A query parameter that was rejected and why.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvalidParam {
+    pub name: String,
+    pub reason: String,
+
+    /// Character offset of the mistake within the value, when it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+/// An RFC 7807 problem document.
+#[derive(Serialize, ToSchema)]
+pub struct Problem<'a> {
+    #[serde(rename = "type")]
+    class: &'a str,
+    title: &'a str,
+    status: u16,
+    detail: String,
+
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    errors: &'a [String],
+
+    #[serde(rename = "invalid-params", skip_serializing_if = "<[InvalidParam]>::is_empty")]
+    invalid_params: &'a [InvalidParam],
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode
+    {
+        match self {
+            Self::Request(..)          => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Connection(..)       => StatusCode::BAD_GATEWAY,
+            Self::Upstream(..)         => StatusCode::BAD_GATEWAY,
+            Self::Timeout(..)          => StatusCode::GATEWAY_TIMEOUT,
+            Self::Unavailable(..)      => StatusCode::SERVICE_UNAVAILABLE,
+            Self::GraphQL(status, ..)  => *status,
+            Self::Decode(..)           => StatusCode::BAD_GATEWAY,
+            Self::Encode(..)           => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound(..)         => StatusCode::NOT_FOUND,
+            Self::BadRequest(..)       => StatusCode::BAD_REQUEST,
+            Self::InvalidParams(..)    => StatusCode::BAD_REQUEST,
+            Self::NotAcceptable(..)    => StatusCode::NOT_ACCEPTABLE,
+            Self::Unauthorized(..)     => StatusCode::UNAUTHORIZED,
+            Self::Unprocessable(..)    => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str
+    {
+        match self {
+            Self::Request(..)          => "request",
+            Self::Connection(..)       => "connection",
+            Self::Upstream(..)         => "status",
+            Self::Timeout(..)          => "timeout",
+            Self::Unavailable(..)      => "breaker_open",
+            Self::GraphQL(..)          => "graphql",
+            Self::Decode(..)           => "decode",
+            Self::Encode(..)           => "encode",
+            Self::NotFound(..)         => "not_found",
+            Self::BadRequest(..)       => "bad_request",
+            Self::InvalidParams(..)    => "invalid_params",
+            Self::NotAcceptable(..)    => "not_acceptable",
+            Self::Unauthorized(..)     => "unauthorized",
+            Self::Unprocessable(..)    => "unprocessable",
+        }
+    }
+
+    /// Whether DataHub, rather than the request, is to blame: the call may
+    /// succeed when repeated and counts against the circuit breaker.
+    pub fn is_transient(&self) -> bool
+    {
+        match self {
+            Self::Connection(..) | Self::Timeout(..) => true,
+            Self::Upstream(status) => status.is_server_error(),
+            _ => false
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(err)         => write!(f, "failed to build upstream request: {err}"),
+            Self::Connection(err)      => write!(f, "failed to reach DataHub: {err}"),
+            Self::Upstream(status)     => write!(f, "DataHub responded with {status}"),
+            Self::Timeout(limit)       => write!(f, "DataHub didn't respond within {}ms", limit.as_millis()),
+            Self::Unavailable(..)      => write!(f, "DataHub is unavailable after repeated failures"),
+            Self::GraphQL(_, messages) => write!(f, "DataHub reported {} error(s)", messages.len()),
+            Self::Decode(err)          => write!(f, "unexpected response from DataHub: {err}"),
+            Self::Encode(msg)          => write!(f, "failed to write the report: {msg}"),
+            Self::NotFound(urn)        => write!(f, "{urn} does not exist"),
+            Self::BadRequest(msg)      => write!(f, "{msg}"),
+            Self::InvalidParams(params) => write!(f, "{} invalid query parameter(s)", params.len()),
+            Self::NotAcceptable(accept) => write!(f, "can't send any of {accept}; \
+                expected application/json, text/csv or application/vnd.apache.parquet"),
+            Self::Unauthorized(msg)    => write!(f, "{msg}"),
+            Self::Unprocessable(msg)   => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response
+    {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::warn!(error = %self, "request failed");
+        }
+        if let Self::GraphQL(_, messages) = &self {
+            messages.iter().for_each(|msg| tracing::warn!(message = %msg, "DataHub error"));
+        }
+
+        let errors = match &self {
+            Self::GraphQL(_, messages) => messages.as_slice(),
+            _ => &[]
+        };
+        let invalid_params = match &self {
+            Self::InvalidParams(params) => params.as_slice(),
+            _ => &[]
+        };
+        let problem = Problem {
+            class: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail: self.to_string(),
+            errors,
+            invalid_params,
+        };
+
+        let mut resp = (status, Json(problem)).into_response();
+        resp.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json")
+        );
+        if status == StatusCode::UNAUTHORIZED {
+            resp.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        if let Self::Unavailable(wait) = &self {
+            let secs = wait.as_secs_f64().ceil() as u64;
+            resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs.max(1)));
+        }
+
+        resp
+    }
+}
+
+impl From<axum::http::Error> for ApiError {
+    fn from(err: axum::http::Error) -> Self
+    {
+        ApiError::Request(err)
+    }
+}
+
+impl From<hyper::Error> for ApiError {
+    fn from(err: hyper::Error) -> Self
+    {
+        ApiError::Connection(err)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(err: JsonRejection) -> Self
+    {
+        ApiError::BadRequest(format!("invalid request body: {err}"))
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(err: PathRejection) -> Self
+    {
+        ApiError::BadRequest(format!("invalid path: {err}"))
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(err: serde_json::Error) -> Self
+    {
+        ApiError::Decode(err)
+    }
+}
+
+impl From<csv::Error> for ApiError {
+    fn from(err: csv::Error) -> Self
+    {
+        ApiError::Encode(err.to_string())
+    }
+}
+
+impl From<parquet::errors::ParquetError> for ApiError {
+    fn from(err: parquet::errors::ParquetError) -> Self
+    {
+        ApiError::Encode(err.to_string())
+    }
+}
+
+impl<'a> From<&[ErrorMessage<'a>]> for ApiError {
+    fn from(errors: &[ErrorMessage<'a>]) -> Self
+    {
+        // DataHub reports an HTTP-like code in the error extensions; the
+        // most severe one wins and anything unrecognised is a server error.
+        let status = errors.iter()
+            .map(|e| e.extensions.as_ref()
+                .and_then(|ext| ext.code)
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .filter(|s| s.is_client_error() || s.is_server_error())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+            )
+            .max()
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let messages = errors.iter()
+            .map(|e| e.message.to_string())
+            .collect();
+
+        ApiError::GraphQL(status, messages)
+    }
+}
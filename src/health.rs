@@ -0,0 +1,139 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json,
+    extract::Extension,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::backend::Backend;
+use crate::config::HealthConfig;
+use crate::error::ApiError;
+
+
+/// Readiness of the backend's dependencies. Probe results are reused for
+/// `window` so frequent polling doesn't turn into load on DataHub.
+pub struct Health {
+    backend: Backend,
+    window: Duration,
+    timeout: Duration,
+    last: Mutex<Option<(Instant, Arc<Readiness>)>>,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    status: Status,
+    dependencies: BTreeMap<&'static str, Check>,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    #[serde(skip)]
+    name: &'static str,
+    status: Status,
+    latency_ms: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Up,
+    Down,
+}
+
+impl Health {
+    pub fn new(backend: Backend, config: &HealthConfig) -> Health
+    {
+        Health {
+            backend,
+            window: Duration::from_secs(config.window),
+            timeout: Duration::from_secs(config.timeout),
+            last: Mutex::new(None),
+        }
+    }
+
+    async fn readiness(&self) -> Arc<Readiness>
+    {
+        // Holding the lock while probing makes concurrent callers share
+        // one round of probes.
+        let mut last = self.last.lock().await;
+        if let Some((at, readiness)) = last.as_ref() {
+            if at.elapsed() < self.window {
+                return readiness.clone()
+            }
+        }
+
+        let checks = self.backend.probe(self.timeout).await;
+        let status = if checks.iter().all(|c| c.status == Status::Up) { Status::Up } else { Status::Down };
+        let readiness = Arc::new(Readiness {
+            status,
+            dependencies: checks.into_iter().map(|c| (c.name, c)).collect(),
+        });
+        *last = Some((Instant::now(), readiness.clone()));
+
+        readiness
+    }
+}
+
+impl Check {
+    pub fn up(name: &'static str, latency: Duration) -> Check
+    {
+        Check { name, status: Status::Up, latency_ms: latency.as_millis() as u64, error: None }
+    }
+
+    pub fn down(name: &'static str, error: String) -> Check
+    {
+        Check { name, status: Status::Down, latency_ms: 0, error: Some(error) }
+    }
+}
+
+/// Runs a probe, reporting the dependency down when it fails or doesn't
+/// answer within the timeout.
+pub async fn check<T>(
+    name: &'static str,
+    timeout: Duration,
+    probe: impl Future<Output = Result<T, ApiError>>
+) -> Check
+{
+    let start = Instant::now();
+    let error = match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(..))  => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(..) => Some(format!("no response within {}s", timeout.as_secs())),
+    };
+
+    Check {
+        name,
+        status: if error.is_none() { Status::Up } else { Status::Down },
+        latency_ms: start.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+pub async fn liveness() -> Json<Value>
+{
+    Json(json!({ "status": Status::Up }))
+}
+
+pub async fn readiness(Extension(health): Extension<Arc<Health>>) -> Response
+{
+    let readiness = health.readiness().await;
+    let status = match readiness.status {
+        Status::Up   => StatusCode::OK,
+        Status::Down => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(&*readiness)).into_response()
+}
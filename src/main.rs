@@ -1,50 +1,122 @@
 mod api;
+mod auth;
+mod backend;
+mod breaker;
+mod cache;
+mod config;
+mod context;
 mod datahub;
+mod error;
+mod health;
+mod metrics;
 mod schemas;
+mod telemetry;
+mod tls;
 
-use std::net::SocketAddr;
+use std::sync::Arc;
 
 use axum::{
     extract::Extension,
     handler::Handler,
     http::{StatusCode, Uri},
+    middleware::from_fn,
     response::{Html, IntoResponse},
     routing::get,
 };
-use hyper::{
-    Body,
-    client::HttpConnector,
-};
 
-type Client = hyper::client::Client<HttpConnector, Body>;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+
+use crate::auth::Authenticator;
+use crate::backend::{Backend, DataHubBackend, MemoryBackend};
+use crate::cache::Cache;
+use crate::config::{BackendKind, Config, ConfigError};
+use crate::health::Health;
 
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>>
 {
+    let config = configured(Config::load());
+    telemetry::init(&config.log);
+    let addr = config.bind_addr()?;
+
+    let listener_tls = config.server.tls.clone()
+        .map(|tls| {
+            let server = configured(tls::server_config(&tls));
+            (tls, RustlsConfig::from_config(Arc::new(server)))
+        });
+
+    let auth = Arc::new(configured(Authenticator::new(&config.auth)));
+    let cache = Arc::new(configured(Cache::new(&config.cache)));
+
+    let backend: Backend = match (config.backend.kind, &config.backend.fixture) {
+        (BackendKind::Memory, Some(fixture)) => Arc::new(configured(MemoryBackend::from_file(fixture))),
+        _ => {
+            let client = configured(datahub::client(&config.datahub));
+            Arc::new(DataHubBackend::new(client, config.datahub.clone()))
+        },
+    };
+
+    let health = Arc::new(Health::new(backend.clone(), &config.health));
+
     let app = axum::Router::new()
         .route("/", get(root))
-        .nest("/api", api::routes())
-        .layer(Extension(Client::new()))
+        .route("/healthz", get(health::liveness))
+        .route("/readyz", get(health::readiness))
+        .route("/metrics", get(metrics::export))
+        .nest("/api", api::routes(backend))
+        .layer(Extension(auth))
+        .layer(Extension(health))
+        .layer(Extension(cache))
+        .layer(Extension(Arc::new(config)))
+        .layer(from_fn(telemetry::request_id))
         .fallback(not_found.into_service());
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    println!("Listening on {addr}");
+    match listener_tls {
+        Some((tls, rustls)) => {
+            tokio::spawn(tls::reload(tls, rustls.clone()));
+
+            let handle = Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .with_graceful_shutdown(shutdown())
-        .await?;
+            tracing::info!(%addr, "listening with TLS");
+            axum_server::bind_rustls(addr, rustls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        },
+        None => {
+            tracing::info!(%addr, "listening");
+            axum::Server::bind(&addr)
+                .serve(app.into_make_service())
+                .with_graceful_shutdown(shutdown())
+                .await?;
+        }
+    }
 
     Ok(())
 }
 
+fn configured<T>(result: Result<T, ConfigError>) -> T
+{
+    result.unwrap_or_else(|err| {
+        eprintln!("Configuration error: {err}");
+        std::process::exit(2)
+    })
+}
+
 async fn shutdown()
 {
     tokio::signal::ctrl_c()
         .await
         .expect("tokio signal ctrl-c");
-    println!("\nShutting down...");
+    tracing::info!("shutting down");
 }
 
 async fn root() -> Html<&'static str>
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::breaker::State;
+use crate::error::ApiError;
+
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_seconds: HistogramVec,
+    in_flight: IntGaugeVec,
+    upstream_seconds: HistogramVec,
+    upstream_errors: IntCounterVec,
+    upstream_retries: IntCounterVec,
+    breaker_state: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Metrics
+    {
+        let requests = IntCounterVec::new(
+            Opts::new("datasvc_requests_total", "Requests handled, by route and status"),
+            &["route", "method", "status"]
+        ).unwrap();
+        let request_seconds = HistogramVec::new(
+            HistogramOpts::new("datasvc_request_duration_seconds", "Request latency, by route and status"),
+            &["route", "method", "status"]
+        ).unwrap();
+        let in_flight = IntGaugeVec::new(
+            Opts::new("datasvc_requests_in_flight", "Requests being handled, by route"),
+            &["route", "method"]
+        ).unwrap();
+        let upstream_seconds = HistogramVec::new(
+            HistogramOpts::new("datasvc_upstream_duration_seconds", "DataHub call latency, by GraphQL operation"),
+            &["operation"]
+        ).unwrap();
+        let upstream_errors = IntCounterVec::new(
+            Opts::new("datasvc_upstream_errors_total", "Failed DataHub calls, by GraphQL operation and kind"),
+            &["operation", "kind"]
+        ).unwrap();
+        let upstream_retries = IntCounterVec::new(
+            Opts::new("datasvc_upstream_retries_total", "Retried DataHub calls, by GraphQL operation"),
+            &["operation"]
+        ).unwrap();
+        let breaker_state = IntGauge::new(
+            "datasvc_upstream_breaker_state",
+            "DataHub circuit breaker state: 0 closed, 1 half open, 2 open"
+        ).unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(request_seconds.clone())).unwrap();
+        registry.register(Box::new(in_flight.clone())).unwrap();
+        registry.register(Box::new(upstream_seconds.clone())).unwrap();
+        registry.register(Box::new(upstream_errors.clone())).unwrap();
+        registry.register(Box::new(upstream_retries.clone())).unwrap();
+        registry.register(Box::new(breaker_state.clone())).unwrap();
+
+        Metrics {
+            registry,
+            requests,
+            request_seconds,
+            in_flight,
+            upstream_seconds,
+            upstream_errors,
+            upstream_retries,
+            breaker_state,
+        }
+    }
+
+    /// Marks a request as in flight until the returned guard is dropped.
+    pub fn started(&self, route: &str, method: &str) -> InFlight
+    {
+        let gauge = self.in_flight.with_label_values(&[route, method]);
+        gauge.inc();
+
+        InFlight(gauge)
+    }
+
+    pub fn handled(&self, route: &str, method: &str, status: StatusCode, elapsed: Duration)
+    {
+        let labels = [route, method, status.as_str()];
+        self.requests.with_label_values(&labels).inc();
+        self.request_seconds.with_label_values(&labels).observe(elapsed.as_secs_f64());
+    }
+
+    pub fn upstream<T>(&self, operation: &str, elapsed: Duration, result: &Result<T, ApiError>)
+    {
+        self.upstream_seconds.with_label_values(&[operation]).observe(elapsed.as_secs_f64());
+        if let Err(err) = result {
+            self.upstream_errors.with_label_values(&[operation, err.kind()]).inc();
+        }
+    }
+
+    /// Counts a call the circuit breaker refused to make.
+    pub fn rejected(&self, operation: &str, err: &ApiError)
+    {
+        self.upstream_errors.with_label_values(&[operation, err.kind()]).inc();
+    }
+
+    pub fn retried(&self, operation: &str)
+    {
+        self.upstream_retries.with_label_values(&[operation]).inc();
+    }
+
+    pub fn breaker(&self, state: &State)
+    {
+        self.breaker_state.set(match state {
+            State::Closed { .. }   => 0,
+            State::HalfOpen { .. } => 1,
+            State::Open { .. }     => 2,
+        });
+    }
+}
+
+pub struct InFlight(IntGauge);
+
+impl Drop for InFlight {
+    fn drop(&mut self)
+    {
+        self.0.dec();
+    }
+}
+
+pub async fn export() -> Response
+{
+    let mut buf = Vec::new();
+    let encoder = TextEncoder::new();
+    match encoder.encode(&METRICS.registry.gather(), &mut buf) {
+        Ok(..) => {
+            let content_type = HeaderValue::from_str(encoder.format_type()).unwrap();
+            ([(header::CONTENT_TYPE, content_type)], buf).into_response()
+        },
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+
+/// Search results counted by the values of one field.
+pub type Aggregations = BTreeMap<&'static str, Vec<Bucket>>;
+
+#[derive(Serialize, ToSchema)]
+pub struct Bucket {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Platform,
+    Tags,
+    Origin,
+    Subtype,
+}
+
+impl Facet {
+    pub const ALL: [Facet; 4] = [Facet::Platform, Facet::Tags, Facet::Origin, Facet::Subtype];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Facet::Platform => "platform",
+            Facet::Tags     => "tags",
+            Facet::Origin   => "origin",
+            Facet::Subtype  => "subtype",
+        }
+    }
+
+    /// The field DataHub aggregates the facet on.
+    pub fn field(&self) -> &'static str
+    {
+        match self {
+            Facet::Platform => "platform",
+            Facet::Tags     => "tags",
+            Facet::Origin   => "origin",
+            Facet::Subtype  => "typeNames",
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::convert::From;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::schemas::{collection::{name_key, Collection}, datahub, paging, Aggregations, Bucket, SortKey, tags};
+
+#[derive(Serialize, ToSchema)]
+pub struct Charts {
+    data: Vec<ChartEnvelope>,
+    paging: Option<paging::Paging>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<Bucket>>>)]
+    aggregations: Option<Aggregations>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ChartEnvelope {
+    chart: Option<Chart>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Chart {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+
+    /// The chart in its BI tool.
+    url: Option<String>,
+
+    /// The kind of chart, e.g. BAR or TABLE.
+    #[serde(rename = "type")]
+    class: Option<String>,
+
+    platform: Option<String>,
+
+    #[serde(rename = "platformType")]
+    platform_type: Option<String>,
+
+    #[serde(rename = "platformName")]
+    platform_name: Option<String>,
+
+    tags: Vec<tags::TagEnvelope>,
+}
+
+impl<'a> TryFrom<&datahub::Entity<'a>> for ChartEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
+    {
+        match e {
+            datahub::Entity::Chart(chart) => Ok(ChartEnvelope::from(chart)),
+            _ => Err(datahub::UnexpectedEntity)
+        }
+    }
+}
+
+impl<'a> From<&datahub::Chart<'a>> for ChartEnvelope {
+    fn from(chart: &datahub::Chart) -> Self
+    {
+        ChartEnvelope {
+            chart: Some(Chart::from(chart))
+        }
+    }
+}
+
+impl<'a> From<&datahub::Chart<'a>> for Chart {
+    fn from(e: &datahub::Chart) -> Self
+    {
+        Chart {
+            id: e.urn.to_owned(),
+            name: e.properties.as_ref()
+                .map(|p| p.name.to_string()),
+            description: e.properties.as_ref()
+                .and_then(|p| p.description.as_deref().map(str::to_owned)),
+            url: e.properties.as_ref()
+                .and_then(|p| p.url.as_deref().map(str::to_owned)),
+            class: e.properties.as_ref()
+                .and_then(|p| p.class.map(str::to_owned)),
+            platform: e.platform.as_ref()
+                .map(|p| p.name.to_owned()),
+            platform_name: e.platform.as_ref()
+                .map(|p| p.properties.name.to_owned()),
+            platform_type: e.platform.as_ref()
+                .map(|p| p.properties.class.to_owned()),
+            tags: e.tags.as_ref()
+                .map_or_else(Vec::new, |tags| tags.tags.iter()
+                    .map(tags::TagEnvelope::from)
+                    .collect()
+                ),
+        }
+    }
+}
+
+impl<'a> From<&datahub::QueryResponse<'a>> for Charts {
+    fn from(resp: &datahub::QueryResponse) -> Self
+    {
+        let (data, paging) = resp.process::<ChartEnvelope>();
+        Charts { data, paging, aggregations: None }
+    }
+}
+
+impl Collection for Charts {
+    type Item = ChartEnvelope;
+
+    fn parts(&mut self) -> (&mut Vec<ChartEnvelope>, &mut Option<paging::Paging>, &mut Option<Aggregations>)
+    {
+        (&mut self.data, &mut self.paging, &mut self.aggregations)
+    }
+
+    fn sort_key(e: &ChartEnvelope, _: SortKey) -> Option<String>
+    {
+        e.chart.as_ref().and_then(|chart| name_key(&chart.id, chart.name.as_ref()))
+    }
+}
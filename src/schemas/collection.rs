@@ -0,0 +1,53 @@
+use crate::schemas::{datahub, paging::Paging, Aggregations, Facet, Links, Sort, SortKey};
+
+
+/// A page of listed entities with its paging block and facet counts, which
+/// every listing fills in the same way.
+pub trait Collection: Sized {
+    type Item;
+
+    fn parts(&mut self) -> (&mut Vec<Self::Item>, &mut Option<Paging>, &mut Option<Aggregations>);
+
+    /// What an item is ordered by when the service sorts by `key`.
+    fn sort_key(item: &Self::Item, key: SortKey) -> Option<String>;
+
+    /// Points the paging block at the pages either side of this one.
+    fn link(mut self, links: &Links) -> Self
+    {
+        if let (_, Some(paging), _) = self.parts() {
+            paging.link(links);
+        }
+        self
+    }
+
+    /// Orders the items in the service, for results DataHub can't sort.
+    fn sort(mut self, sort: Option<Sort>) -> Self
+    {
+        if let Some(sort) = sort {
+            let key = |item: &Self::Item| Self::sort_key(item, sort.key);
+            self.parts().0.sort_by(|a, b| {
+                let order = key(a).cmp(&key(b));
+                if sort.descending { order.reverse() } else { order }
+            });
+        }
+        self
+    }
+
+    /// Adds DataHub's counts for each of the requested facets.
+    fn aggregate(mut self, resp: &datahub::QueryResponse, facets: &[Facet]) -> Self
+    {
+        if !facets.is_empty() {
+            *self.parts().2 = Some(facets.iter()
+                .map(|f| (f.name(), resp.buckets(f.field())))
+                .collect()
+            );
+        }
+        self
+    }
+}
+
+/// How entities known by name are ordered: by name, or by id without one.
+pub fn name_key(id: &str, name: Option<&String>) -> Option<String>
+{
+    Some(name.map_or(id, String::as_str).to_lowercase())
+}
@@ -0,0 +1,109 @@
+use std::convert::From;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::schemas::{collection::{name_key, Collection}, datahub, paging, Aggregations, Bucket, SortKey, tags};
+
+#[derive(Serialize, ToSchema)]
+pub struct Dashboards {
+    data: Vec<DashboardEnvelope>,
+    paging: Option<paging::Paging>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<Bucket>>>)]
+    aggregations: Option<Aggregations>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DashboardEnvelope {
+    dashboard: Option<Dashboard>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Dashboard {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+
+    /// The dashboard in its BI tool.
+    url: Option<String>,
+
+    platform: Option<String>,
+
+    #[serde(rename = "platformType")]
+    platform_type: Option<String>,
+
+    #[serde(rename = "platformName")]
+    platform_name: Option<String>,
+
+    tags: Vec<tags::TagEnvelope>,
+}
+
+impl<'a> TryFrom<&datahub::Entity<'a>> for DashboardEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
+    {
+        match e {
+            datahub::Entity::Dashboard(dashboard) => Ok(DashboardEnvelope::from(dashboard)),
+            _ => Err(datahub::UnexpectedEntity)
+        }
+    }
+}
+
+impl<'a> From<&datahub::Dashboard<'a>> for DashboardEnvelope {
+    fn from(dashboard: &datahub::Dashboard) -> Self
+    {
+        DashboardEnvelope {
+            dashboard: Some(Dashboard::from(dashboard))
+        }
+    }
+}
+
+impl<'a> From<&datahub::Dashboard<'a>> for Dashboard {
+    fn from(e: &datahub::Dashboard) -> Self
+    {
+        Dashboard {
+            id: e.urn.to_owned(),
+            name: e.properties.as_ref()
+                .map(|p| p.name.to_string()),
+            description: e.properties.as_ref()
+                .and_then(|p| p.description.as_deref().map(str::to_owned)),
+            url: e.properties.as_ref()
+                .and_then(|p| p.url.as_deref().map(str::to_owned)),
+            platform: e.platform.as_ref()
+                .map(|p| p.name.to_owned()),
+            platform_name: e.platform.as_ref()
+                .map(|p| p.properties.name.to_owned()),
+            platform_type: e.platform.as_ref()
+                .map(|p| p.properties.class.to_owned()),
+            tags: e.tags.as_ref()
+                .map_or_else(Vec::new, |tags| tags.tags.iter()
+                    .map(tags::TagEnvelope::from)
+                    .collect()
+                ),
+        }
+    }
+}
+
+impl<'a> From<&datahub::QueryResponse<'a>> for Dashboards {
+    fn from(resp: &datahub::QueryResponse) -> Self
+    {
+        let (data, paging) = resp.process::<DashboardEnvelope>();
+        Dashboards { data, paging, aggregations: None }
+    }
+}
+
+impl Collection for Dashboards {
+    type Item = DashboardEnvelope;
+
+    fn parts(&mut self) -> (&mut Vec<DashboardEnvelope>, &mut Option<paging::Paging>, &mut Option<Aggregations>)
+    {
+        (&mut self.data, &mut self.paging, &mut self.aggregations)
+    }
+
+    fn sort_key(e: &DashboardEnvelope, _: SortKey) -> Option<String>
+    {
+        e.dashboard.as_ref().and_then(|dashboard| name_key(&dashboard.id, dashboard.name.as_ref()))
+    }
+}
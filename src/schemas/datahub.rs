@@ -1,8 +1,7 @@
-use std::{fmt, convert::From};
+use std::{fmt, borrow::Cow};
 use serde::{Deserialize, Serialize};
 
-use crate::schemas::Paging;
-use crate::schemas::{PlatformEnvelope};
+use crate::schemas::{Bucket, Paging};
 
 #[derive(Deserialize)]
 pub struct Tags<'a> {
@@ -12,7 +11,7 @@ pub struct Tags<'a> {
 
 #[derive(Deserialize)]
 pub struct TagEntity<'a> {
-    #[serde(borrow)]
+    #[serde(borrow, alias = "tag")]
     pub entity: Option<Tag<'a>>,
 }
 
@@ -24,8 +23,10 @@ pub struct Tag<'a> {
 
 #[derive(Deserialize)]
 pub struct TagProperties<'a> {
-    pub name: &'a str,
-    pub description: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub description: Cow<'a, str>,
 }
 
 #[derive(Deserialize)]
@@ -37,7 +38,8 @@ pub struct DatasetEntity<'a> {
 #[derive(Deserialize)]
 pub struct Dataset<'a> {
     pub urn: &'a str,
-    pub name: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
     pub tags: Option<Tags<'a>>,
     pub schema: Option<DatasetSchema<'a>>,
     pub platform: Option<DatasetPlatform<'a>>,
@@ -65,9 +67,12 @@ pub struct DatasetPlatformProperties<'a> {
 
 #[derive(Deserialize)]
 pub struct DatasetField<'a> {
-    pub path: &'a str,
-    pub class: &'a str,
-    pub native: &'a str,
+    #[serde(borrow)]
+    pub path: Cow<'a, str>,
+    #[serde(borrow)]
+    pub class: Cow<'a, str>,
+    #[serde(borrow)]
+    pub native: Cow<'a, str>,
 }
 
 #[derive(Deserialize)]
@@ -78,13 +83,141 @@ pub struct DatasetSubType<'a> {
 
 #[derive(Deserialize)]
 pub struct DatasetProperties<'a> {
-    pub name: &'a str,
-    pub origin: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub origin: Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+pub struct Chart<'a> {
+    pub urn: &'a str,
+    pub tags: Option<Tags<'a>>,
+    pub platform: Option<DatasetPlatform<'a>>,
+    pub properties: Option<ChartProperties<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct ChartProperties<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub description: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub url: Option<Cow<'a, str>>,
+    pub class: Option<&'a str>,
+}
+
+/// The datasets a chart reads from.
+#[derive(Deserialize)]
+pub struct ChartInputs<'a> {
+    #[serde(borrow)]
+    pub inputs: Relationships<'a>,
+}
+
+#[derive(Deserialize)]
+pub struct Dashboard<'a> {
+    pub urn: &'a str,
+    pub tags: Option<Tags<'a>>,
+    pub platform: Option<DatasetPlatform<'a>>,
+    pub properties: Option<DashboardProperties<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct DashboardProperties<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub description: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub url: Option<Cow<'a, str>>,
+}
+
+#[derive(Deserialize)]
+pub struct DataFlow<'a> {
+    pub urn: &'a str,
+    pub orchestrator: &'a str,
+    pub cluster: Option<&'a str>,
+    pub tags: Option<Tags<'a>>,
+    pub properties: Option<DataFlowProperties<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct DataFlowProperties<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub description: Option<Cow<'a, str>>,
+    pub project: Option<&'a str>,
+    #[serde(borrow)]
+    pub url: Option<Cow<'a, str>>,
+}
+
+/// The jobs a data flow is made up of, or those reading or writing a dataset.
+#[derive(Deserialize)]
+pub struct RelatedJobs<'a> {
+    #[serde(borrow)]
+    pub jobs: Relationships<'a>,
+}
+
+#[derive(Deserialize)]
+pub struct DataJob<'a> {
+    pub urn: &'a str,
+    pub properties: Option<DataJobProperties<'a>>,
+
+    #[serde(borrow)]
+    pub io: Option<DataJobInputOutput<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct DataJobProperties<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub description: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub url: Option<Cow<'a, str>>,
+}
+
+#[derive(Deserialize)]
+pub struct DataJobInputOutput<'a> {
+    #[serde(borrow)]
+    pub inputs: Option<Vec<Dataset<'a>>>,
+
+    #[serde(borrow)]
+    pub outputs: Option<Vec<Dataset<'a>>>,
+}
+
+#[derive(Deserialize)]
+pub struct Relationships<'a> {
+    pub total: i32,
+
+    #[serde(borrow)]
+    pub relationships: Vec<Relationship<'a>>,
+}
+
+#[derive(Deserialize)]
+pub struct Relationship<'a> {
+    #[serde(borrow)]
+    pub entity: Entity<'a>,
 }
 
 #[derive(Deserialize)]
 pub struct ErrorMessage<'a> {
-    pub message: &'a str
+    #[serde(borrow)]
+    pub message: Cow<'a, str>,
+    pub extensions: Option<ErrorExtensions>,
+}
+
+#[derive(Deserialize)]
+pub struct ErrorExtensions {
+    pub code: Option<u16>,
+}
+
+#[derive(Deserialize)]
+pub struct ErrorResponse<'a> {
+    #[serde(borrow)]
+    pub errors: Option<Vec<ErrorMessage<'a>>>,
 }
 
 
@@ -100,6 +233,8 @@ struct QueryResponseData<'a> {
     results: QueryResults<'a>
 }
 
+// Variants are named for the `__typename` DataHub tags them with.
+#[allow(clippy::enum_variant_names)]
 #[derive(Deserialize)]
 #[serde(tag = "__typename")]
 enum QueryResults<'a> {
@@ -111,8 +246,27 @@ enum QueryResults<'a> {
         count: i32,
         total: i32,
 
+        // Not DataHub's: the scroll id the service adds to a searched page
+        // whose next page only a cursor reaches.
+        #[serde(default)]
+        next: Option<&'a str>,
+
         #[serde(borrow)]
-        entities: Vec<EntityEnvelope<'a>>
+        entities: Vec<EntityEnvelope<'a>>,
+
+        #[serde(borrow, default)]
+        facets: Vec<Facet<'a>>,
+    },
+    ScrollResults {
+        next: Option<&'a str>,
+        count: i32,
+        total: i32,
+
+        #[serde(borrow)]
+        entities: Vec<EntityEnvelope<'a>>,
+
+        #[serde(borrow, default)]
+        facets: Vec<Facet<'a>>,
     },
 }
 
@@ -129,11 +283,34 @@ pub enum Entity<'a> {
 
     #[serde(borrow)]
     Dataset(Dataset<'a>),
+
+    #[serde(borrow)]
+    DataPlatform(DataPlatform<'a>),
+
+    #[serde(borrow)]
+    Chart(Chart<'a>),
+
+    #[serde(borrow)]
+    Dashboard(Dashboard<'a>),
+
+    #[serde(borrow)]
+    DataFlow(DataFlow<'a>),
+
+    #[serde(borrow)]
+    DataJob(DataJob<'a>),
+
+    // Entity types the service has no use for, e.g. in relationships.
+    #[serde(other)]
+    Unknown,
 }
 
+/// An entity of a different type than the one being converted to.
+#[derive(Debug)]
+pub struct UnexpectedEntity;
+
 impl<'a> QueryResponse<'a> {
     pub fn process<T>(&'a self) -> (Vec<T>, Option<Paging>)
-        where T: From<&'a Entity<'a>>
+        where T: TryFrom<&'a Entity<'a>>
     {
         (
             self.data.results.process::<T>(),
@@ -142,6 +319,40 @@ impl<'a> QueryResponse<'a> {
     }
 }
 
+impl<'a> QueryResponse<'a> {
+    /// DataHub's counts for each value of `field` across every match.
+    pub fn buckets(&self, field: &str) -> Vec<Bucket>
+    {
+        match &self.data.results {
+            QueryResults::SearchResults { facets, .. }
+            | QueryResults::ScrollResults { facets, .. } => buckets(facets, field),
+            QueryResults::AutoCompleteResults { .. } => vec![],
+        }
+    }
+}
+
+impl<'a> QueryResponse<'a> {
+    /// The number of results on the page and in all, unless autocompleted.
+    pub fn extent(&self) -> Option<(i32, i32)>
+    {
+        match &self.data.results {
+            QueryResults::SearchResults { count, total, .. }
+            | QueryResults::ScrollResults { count, total, .. } => Some((*count, *total)),
+            QueryResults::AutoCompleteResults { .. } => None,
+        }
+    }
+
+    /// DataHub's id for scrolling on to the next page, if there is one.
+    pub fn scroll_id(&self) -> Option<&'a str>
+    {
+        match &self.data.results {
+            QueryResults::SearchResults { next, .. }
+            | QueryResults::ScrollResults { next, .. } => *next,
+            _ => None
+        }
+    }
+}
+
 impl<'a> QueryResults<'a> {
     fn paging(&self) -> Option<Paging> {
         match self {
@@ -149,22 +360,25 @@ impl<'a> QueryResults<'a> {
             Self::SearchResults { start, count, total, .. } => {
                 Some(Paging::new(*start, *count, *total))
             },
+            // Only the caller knows where a scrolled page starts.
+            Self::ScrollResults { count, total, .. } => Some(Paging::new(0, *count, *total)),
         }
     }
 
+    // Entities of other types than `T` are skipped.
     fn process<T>(&'a self) -> Vec<T>
-        where T: From<&'a Entity<'a>>
+        where T: TryFrom<&'a Entity<'a>>
     {
         match self {
             Self::AutoCompleteResults { entities } => {
                 entities.iter()
-                    .map(T::from)
+                    .filter_map(|e| T::try_from(e).ok())
                     .collect()
             },
-            Self::SearchResults { entities, .. } => {
+            Self::SearchResults { entities, .. } | Self::ScrollResults { entities, .. } => {
                 entities.iter()
                     .map(|e| &e.entity)
-                    .map(T::from)
+                    .filter_map(|e| T::try_from(e).ok())
                     .collect()
             },
         }
@@ -173,11 +387,8 @@ impl<'a> QueryResults<'a> {
 
 
 #[derive(Deserialize)]
-pub struct DatasetAddTagResponse<'a> {
+pub struct DatasetAddTagResponse {
     pub data: Option<DatasetAddTagResult>,
-
-    #[serde(borrow)]
-    pub errors: Option<Vec<ErrorMessage<'a>>>,
 }
 
 #[derive(Deserialize)]
@@ -227,7 +438,7 @@ pub struct DeleteTag<'a> {
 }
 
 impl<'a> DeleteTag<'a> {
-    pub fn new(urn: &str) -> DeleteTag
+    pub fn new(urn: &str) -> DeleteTag<'_>
     {
         DeleteTag {
             entity: Value { 
@@ -262,7 +473,7 @@ struct Value<'a> {
 
 #[derive(Serialize)]
 struct Snapshot<'a> {
-    #[serde(rename(serialize = "com.linkedin.metadata.snapshot.TagSnapshot"))]
+    #[serde(rename = "com.linkedin.metadata.snapshot.TagSnapshot")]
     snapshot: SnapshotValues<'a>,
 }
 
@@ -274,10 +485,10 @@ struct SnapshotValues<'a> {
 
 #[derive(Serialize)]
 enum Aspect<'a> {
-    #[serde(rename(serialize = "com.linkedin.common.Status"))]
+    #[serde(rename = "com.linkedin.common.Status")]
     Status { removed: bool },
     
-    #[serde(rename(serialize = "com.linkedin.tag.TagProperties"))]
+    #[serde(rename = "com.linkedin.tag.TagProperties")]
     Properties {
         name: &'a str,
         description: &'a str
@@ -286,76 +497,62 @@ enum Aspect<'a> {
 
 
 #[derive(Deserialize)]
-pub struct ListRecommendationsResponse<'a> {
-    #[serde(borrow)]
-    data: RecommendationsResponseData<'a>
+pub struct DataPlatform<'a> {
+    pub urn: &'a str,
+    pub name: &'a str,
+    pub properties: PlatformProperties<'a>
 }
 
 #[derive(Deserialize)]
-struct RecommendationsResponseData<'a> {
-    #[serde(borrow)]
-    results: RecommendationsModules<'a>
+pub struct PlatformProperties<'a> {
+    pub name: &'a str,
+    pub class: &'a str,
+    pub logo: Option<&'a str>,
 }
 
+
+/// The total of a search that asks for no entities.
 #[derive(Deserialize)]
-struct RecommendationsModules<'a> {
-    #[serde(borrow)]
-    modules: Vec<RecommendationsModule<'a>>,
+pub struct CountResponse {
+    data: CountResponseData
 }
 
 #[derive(Deserialize)]
-#[serde(tag = "id")]
-enum RecommendationsModule<'a> {
-    Platforms { 
-        #[serde(borrow)]
-        content: Vec<PlatformEntity<'a>>
-    },
-    RecentlyViewedEntities,
-    HighUsageEntities,
-    TopTags,
+struct CountResponseData {
+    results: CountResults
 }
 
 #[derive(Deserialize)]
-pub struct PlatformEntity<'a> {
-    #[serde(borrow)]
-    pub entity: DataPlatform<'a>,
+struct CountResults {
+    total: i64,
 }
 
-#[derive(Deserialize)]
-pub struct DataPlatform<'a> {
-    pub urn: &'a str,
-    pub name: &'a str,
-    pub properties: PlatformProperties<'a>
+impl CountResponse {
+    pub fn total(&self) -> i64
+    {
+        self.data.results.total
+    }
 }
 
 #[derive(Deserialize)]
-pub struct PlatformProperties<'a> {
-    pub name: &'a str,
-    pub class: &'a str,
-}
+struct Facet<'a> {
+    field: &'a str,
 
-impl<'a> ListRecommendationsResponse<'a> {
-    pub fn process(&self) -> (Vec<PlatformEnvelope>, Option<Paging>)
-    {
-        (
-            self.data.results.modules.iter()
-                .flat_map(|m| m.process())
-                .collect(),
-            None
-        )
-    }
+    #[serde(borrow)]
+    aggregations: Vec<Aggregation<'a>>,
 }
 
-impl<'a> RecommendationsModule<'a> {
-    fn process(&self) -> Vec<PlatformEnvelope>
-    {
-        match self {
-            Self::Platforms { content } => {
-                content.iter()
-                    .map(PlatformEnvelope::from)
-                    .collect()
-            },
-            _ => vec![]
-        }
-    }
+#[derive(Deserialize)]
+struct Aggregation<'a> {
+    value: &'a str,
+    count: i64,
+}
+
+fn buckets(facets: &[Facet], field: &str) -> Vec<Bucket>
+{
+    facets.iter()
+        .filter(|f| f.field == field)
+        .flat_map(|f| &f.aggregations)
+        .map(|a| Bucket { value: a.value.to_owned(), count: a.count })
+        .collect()
 }
@@ -1,49 +1,133 @@
-use serde::Serialize;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use utoipa::ToSchema;
 use std::convert::From;
 
-use crate::schemas::{datahub, paging, tags};
+use crate::schemas::{collection::Collection, datahub, paging, Aggregations, Bucket, SortKey, tags};
 
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Datasets {
     data: Vec<DatasetEnvelope>,
     paging: Option<paging::Paging>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<Bucket>>>)]
+    aggregations: Option<Aggregations>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DatasetEnvelope {
     pub dataset: Option<Dataset>
 }
 
-#[derive(Serialize)]
+/// Serialized with only the fields in its projection, so `id` is the only
+/// one that's always present.
+#[derive(ToSchema)]
 pub struct Dataset {
+    #[serde(skip)]
+    projection: Projection,
+
     id: String,
     path: String,
     name: Option<String>,
     origin: Option<String>,
     platform: Option<String>,
 
-    #[serde(rename(serialize = "platformType"))]
+    #[serde(rename = "platformType")]
     platform_type: Option<String>,
 
-    #[serde(rename(serialize = "platformName"))]
+    #[serde(rename = "platformName")]
     platform_name: Option<String>,
 
-    #[serde(rename(serialize = "type"))]
+    #[serde(rename = "type")]
     class: Option<String>,
 
     tags: Vec<tags::TagEnvelope>,
     fields: Option<Vec<Field>>,
 }
 
-#[derive(Serialize)]
+/// Datasets flattened into rows of text for CSV and Parquet reports.
+pub struct Table {
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// The dataset fields a caller asked for, out of `Projection::KEYS`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Projection(u16);
+
+impl Projection {
+    pub const KEYS: [&'static str; 10] = [
+        "id", "path", "name", "origin", "platform", "platformType", "platformName", "type", "tags", "fields",
+    ];
+
+    /// The optional aspects and the fields that come from each.
+    pub const ASPECTS: [(&'static str, &'static [&'static str]); 3] = [
+        ("schema",   &["fields"]),
+        ("tags",     &["tags"]),
+        ("platform", &["platform", "platformType", "platformName"]),
+    ];
+
+    pub const ALL: Projection = Projection((1 << Self::KEYS.len()) - 1);
+
+    /// Just the named fields, which must all be in `KEYS`.
+    pub fn only(keys: &[&str]) -> Projection
+    {
+        Projection(keys.iter().fold(1, |bits, key| bits | Self::bit(key)))
+    }
+
+    pub fn without(self, keys: &[&str]) -> Projection
+    {
+        Projection(keys.iter().fold(self.0, |bits, key| bits & !Self::bit(key)) | 1)
+    }
+
+    pub fn wants(&self, key: &str) -> bool
+    {
+        self.0 & Self::bit(key) != 0
+    }
+
+    fn bit(key: &str) -> u16
+    {
+        Self::KEYS.iter()
+            .position(|k| *k == key)
+            .map_or(0, |i| 1 << i)
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self
+    {
+        Projection::ALL
+    }
+}
+
+impl Serialize for Dataset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let wants = |key| self.projection.wants(key);
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("id", &self.id)?;
+        if wants("path")         { map.serialize_entry("path", &self.path)?; }
+        if wants("name")         { map.serialize_entry("name", &self.name)?; }
+        if wants("origin")       { map.serialize_entry("origin", &self.origin)?; }
+        if wants("platform")     { map.serialize_entry("platform", &self.platform)?; }
+        if wants("platformType") { map.serialize_entry("platformType", &self.platform_type)?; }
+        if wants("platformName") { map.serialize_entry("platformName", &self.platform_name)?; }
+        if wants("type")         { map.serialize_entry("type", &self.class)?; }
+        if wants("tags")         { map.serialize_entry("tags", &self.tags)?; }
+        if wants("fields")       { map.serialize_entry("fields", &self.fields)?; }
+        map.end()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct Field {
     path: String,
 
-    #[serde(rename(serialize = "type"))]
+    #[serde(rename = "type")]
     class: String,
 
-    #[serde(rename(serialize = "nativeType"))]
+    #[serde(rename = "nativeType")]
     native: String,
 }
 
@@ -51,23 +135,24 @@ impl<'a> From<&datahub::DatasetField<'a>> for Field {
     fn from(field: &datahub::DatasetField) -> Self
     {
         Field {
-            path: field.path.to_owned(),
-            class: field.class.to_owned(),
-            native: field.native.to_owned(),
+            path: field.path.to_string(),
+            class: field.class.to_string(),
+            native: field.native.to_string(),
         }
     }
 }
 
-impl<'a> From<&datahub::Entity<'a>> for DatasetEnvelope {
-    fn from(e: &datahub::Entity) -> Self
+impl<'a> TryFrom<&datahub::Entity<'a>> for DatasetEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
     {
         match e {
-            datahub::Entity::Dataset(dataset) => DatasetEnvelope { 
+            datahub::Entity::Dataset(dataset) => Ok(DatasetEnvelope { 
                 dataset: Some(Dataset::from(dataset))
-            },
-            _ => panic!()
+            }),
+            _ => Err(datahub::UnexpectedEntity)
         }
-        
     }
 }
 
@@ -93,14 +178,16 @@ impl<'a> From<&datahub::Dataset<'a>> for Dataset {
     fn from(e: &datahub::Dataset) -> Self
     {
         Dataset {
+            projection: Projection::ALL,
             id: e.urn.to_owned(),
-            path: e.name.to_owned(),
+            path: e.name.to_string(),
             name: e.properties.as_ref()
-                .map(|p| p.name.to_owned()),
+                .map(|p| p.name.to_string()),
             class: e.sub_types.as_ref()
-                .map(|st| st.names[0].to_owned()),
+                .and_then(|st| st.names.first())
+                .map(|name| (*name).to_owned()),
             origin: e.properties.as_ref()
-                .map(|p| p.origin.to_owned()),
+                .map(|p| p.origin.to_string()),
             platform: e.platform.as_ref()
                 .map(|p| p.name.to_owned()),
             platform_name: e.platform.as_ref()
@@ -125,6 +212,116 @@ impl<'a> From<&datahub::QueryResponse<'a>> for Datasets {
     fn from(resp: &datahub::QueryResponse) -> Self
     {
         let (data, paging) = resp.process::<DatasetEnvelope>();
-        Datasets { data, paging }
+        Datasets { data, paging, aggregations: None }
+    }
+}
+
+impl<'a> From<&datahub::Relationships<'a>> for Datasets {
+    fn from(rels: &datahub::Relationships) -> Self
+    {
+        let data: Vec<_> = rels.relationships.iter()
+            .filter_map(|r| DatasetEnvelope::try_from(&r.entity).ok())
+            .collect();
+        let paging = paging::Paging::new(0, data.len() as i32, rels.total);
+
+        Datasets { data, paging: Some(paging), aggregations: None }
+    }
+}
+
+impl Datasets {
+    /// One row per dataset, with a column per projected field in the
+    /// order of `Projection::KEYS`.
+    pub fn dataset_table(&self, projection: Projection) -> Table
+    {
+        let columns: Vec<_> = Projection::KEYS.into_iter()
+            .filter(|key| projection.wants(key))
+            .collect();
+        let rows = self.datasets()
+            .map(|ds| columns.iter().map(|key| ds.column(key)).collect())
+            .collect();
+
+        Table { columns, rows }
+    }
+
+    /// One row per schema field of every dataset.
+    pub fn field_table(&self) -> Table
+    {
+        let rows = self.datasets()
+            .flat_map(|ds| ds.fields.iter().flatten().map(move |f| vec![
+                Some(ds.id.clone()), Some(f.path.clone()), Some(f.class.clone()), Some(f.native.clone())
+            ]))
+            .collect();
+
+        Table { columns: vec!["dataset", "path", "type", "nativeType"], rows }
+    }
+
+    fn datasets(&self) -> impl Iterator<Item = &Dataset>
+    {
+        self.data.iter().filter_map(|e| e.dataset.as_ref())
+    }
+
+    pub fn project(mut self, projection: Projection) -> Self
+    {
+        self.data = self.data.into_iter()
+            .map(|envelope| envelope.project(projection))
+            .collect();
+        self
+    }
+}
+impl Collection for Datasets {
+    type Item = DatasetEnvelope;
+
+    fn parts(&mut self) -> (&mut Vec<DatasetEnvelope>, &mut Option<paging::Paging>, &mut Option<Aggregations>)
+    {
+        (&mut self.data, &mut self.paging, &mut self.aggregations)
+    }
+
+    fn sort_key(e: &DatasetEnvelope, key: SortKey) -> Option<String>
+    {
+        e.dataset.as_ref().and_then(|ds| ds.sort_key(key))
+    }
+}
+
+impl Dataset {
+    // A field's value as report text, with lists joined by `;`.
+    fn column(&self, key: &str) -> Option<String>
+    {
+        match key {
+            "id"           => Some(self.id.clone()),
+            "path"         => Some(self.path.clone()),
+            "name"         => self.name.clone(),
+            "origin"       => self.origin.clone(),
+            "platform"     => self.platform.clone(),
+            "platformType" => self.platform_type.clone(),
+            "platformName" => self.platform_name.clone(),
+            "type"         => self.class.clone(),
+            "tags"         => Some(self.tags.iter().filter_map(tags::TagEnvelope::name).collect::<Vec<_>>().join(";")),
+            "fields"       => self.fields.as_ref()
+                .map(|fields| fields.iter().map(|f| f.path.as_str()).collect::<Vec<_>>().join(";")),
+            _ => None
+        }
+    }
+
+    fn sort_key(&self, key: SortKey) -> Option<String>
+    {
+        match key {
+            SortKey::Name         => Some(self.name.as_ref().unwrap_or(&self.path).to_lowercase()),
+            SortKey::Platform     => self.platform.clone(),
+            SortKey::LastModified => None,
+        }
     }
-}
\ No newline at end of file
+}
+
+impl DatasetEnvelope {
+    pub fn into_dataset(self) -> Option<Dataset>
+    {
+        self.dataset
+    }
+
+    pub fn project(self, projection: Projection) -> Self
+    {
+        DatasetEnvelope {
+            dataset: self.dataset.map(|dataset| Dataset { projection, ..dataset })
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use crate::schemas::Sort;
+
 #[derive(Serialize)]
 pub struct GraphQL<'a> {
     query: &'a str,
@@ -14,67 +16,126 @@ pub enum Variables<'a> {
     #[serde(rename = "input")]
     SearchInput(SearchInput<'a>),
 
+    #[serde(rename = "input")]
+    ScrollInput(ScrollInput<'a>),
+
     #[serde(rename = "input")]
     AutoCompleteInput(AutoCompleteInput<'a>),
 
     #[serde(rename = "input")]
     TagAssociationInput(TagAssociationInput<'a>),
 
-    #[serde(rename = "input")]
-    ListRecommendationsInput(ListRecommendationsInput<'a>),
+    #[serde(untagged)]
+    Related(RelatedInput<'a>),
+}
+
+/// An entity's urn plus the page of its relationships to fetch.
+#[derive(Serialize)]
+pub struct RelatedInput<'a> {
+    pub urn: &'a str,
+    pub start: i32,
+    pub count: i32,
 }
 
 #[derive(Serialize)]
 pub struct AutoCompleteInput<'a> {
-    limit: i32,
-    query: &'a str,
+    pub limit: i32,
+    pub query: &'a str,
 
     #[serde(rename = "type")]
-    class: &'a str,
+    pub class: &'a str,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct SearchInput<'a> {
-    start: i32,
-    count: i32,
-    query: String,
+    pub start: i32,
+    pub count: i32,
+    pub query: String,
 
     #[serde(rename = "type")]
-    class: &'a str,
+    pub class: &'a str,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    filters: Option<Filter<'a>>,
+    #[serde(rename = "orFilters", skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<AndFilter<'a>>,
+
+    #[serde(rename = "sortInput", skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortInput>,
 }
 
-#[derive(Serialize)]
-pub struct Filter<'a> {
-    field: &'a str,
-    value: &'a str,
+#[derive(Clone, Serialize)]
+pub struct SortInput {
+    #[serde(rename = "sortCriterion")]
+    pub criterion: SortCriterion,
 }
 
-#[derive(Serialize)]
-pub struct TagAssociationInput<'a> {
-    #[serde(rename(serialize = "tagUrn"))]
-    tag: &'a str,
+#[derive(Clone, Serialize)]
+pub struct SortCriterion {
+    pub field: &'static str,
 
-    #[serde(rename(serialize = "resourceUrn"))]
-    resource: &'a str
+    #[serde(rename = "sortOrder")]
+    pub order: SortOrder,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// A search continued through DataHub's scroll API, which pages past the
+/// search window but only forwards.
 #[derive(Serialize)]
-pub struct ListRecommendationsInput<'a> {
-    limit: i32,
+pub struct ScrollInput<'a> {
+    pub types: [&'a str; 1],
+    pub query: String,
+    pub count: i32,
+
+    #[serde(rename = "scrollId", skip_serializing_if = "Option::is_none")]
+    pub scroll_id: Option<String>,
+
+    #[serde(rename = "keepAlive")]
+    pub keep_alive: &'static str,
 
-    #[serde(rename(serialize = "userUrn"))]
-    user: &'a str,
+    #[serde(rename = "orFilters", skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<AndFilter<'a>>,
 
-    #[serde(rename(serialize = "requestContext"))]
-    context: RequestContext<'a>,
+    #[serde(rename = "sortInput", skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortInput>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AndFilter<'a> {
+    pub and: Vec<Filter<'a>>,
+}
+
+/// Matches entities with any of `values` in `field`, or with none of them
+/// when negated.
+#[derive(Clone, Serialize)]
+pub struct Filter<'a> {
+    pub field: &'a str,
+    pub values: Vec<String>,
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+/// How a filter value is compared with the field.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Condition {
+    Equal,
+    Contain,
+    StartWith,
+    EndWith,
 }
 
 #[derive(Serialize)]
-struct RequestContext<'a> {
-    scenario: &'a str,
+pub struct TagAssociationInput<'a> {
+    #[serde(rename = "tagUrn")]
+    pub tag: &'a str,
+
+    #[serde(rename = "resourceUrn")]
+    pub resource: &'a str
 }
 
 impl<'a> GraphQL<'a> {
@@ -82,6 +143,21 @@ impl<'a> GraphQL<'a> {
     {
         GraphQL { query, variables: vars }
     }
+
+    pub fn variables(&self) -> &Variables<'a>
+    {
+        &self.variables
+    }
+
+    /// The operation name declared by the query, e.g. `by_id`.
+    pub fn operation(&self) -> &'a str
+    {
+        self.query
+            .split(|c: char| c == '(' || c == '{' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .nth(1)
+            .unwrap_or("anonymous")
+    }
 }
 
 impl<'a> std::fmt::Display for GraphQL<'a> {
@@ -106,17 +182,84 @@ impl<'a> SearchInput<'a> {
         query: String,
         start: i32,
         count: i32,
-        filters: Option<Filter<'a>>
+        filters: &[Filter<'a>]
     ) -> SearchInput<'a>
     {
-        SearchInput { class, query, start, count, filters }
+        // Every filter must hold, so they form a single conjunction.
+        if filters.is_empty() {
+            SearchInput::any_of(class, query, start, count, &[])
+        } else {
+            SearchInput::any_of(class, query, start, count, &[filters.to_vec()])
+        }
+    }
+
+    /// Matches entities satisfying every filter of any one of the groups.
+    pub fn any_of(
+        class: &'a str,
+        query: String,
+        start: i32,
+        count: i32,
+        groups: &[Vec<Filter<'a>>]
+    ) -> SearchInput<'a>
+    {
+        let filters = groups.iter()
+            .map(|and| AndFilter { and: and.clone() })
+            .collect();
+
+        SearchInput { class, query, start, count, filters, sort: None }
+    }
+
+    pub fn sorted(self, sort: Option<Sort>) -> SearchInput<'a>
+    {
+        SearchInput { sort: sort.map(SortInput::from), ..self }
+    }
+}
+
+impl<'a> ScrollInput<'a> {
+    // How long DataHub keeps a scroll open between pages.
+    const KEEP_ALIVE: &'static str = "5m";
+
+    pub fn new(search: SearchInput<'a>, scroll_id: Option<String>) -> ScrollInput<'a>
+    {
+        ScrollInput {
+            types: [search.class],
+            query: search.query,
+            count: search.count,
+            scroll_id,
+            keep_alive: Self::KEEP_ALIVE,
+            filters: search.filters,
+            sort: search.sort,
+        }
+    }
+}
+
+impl From<Sort> for SortInput {
+    fn from(sort: Sort) -> Self
+    {
+        let order = if sort.descending { SortOrder::Descending } else { SortOrder::Ascending };
+        SortInput { criterion: SortCriterion { field: sort.key.field(), order } }
     }
 }
 
 impl<'a> Filter<'a> {
-    pub fn new(field: &'a str, value: &'a str) -> Filter<'a>
+    pub fn new(field: &'a str, value: &str) -> Filter<'a>
     {
-        Filter { field, value }
+        Filter::any(field, vec![value.to_owned()])
+    }
+
+    pub fn any(field: &'a str, values: Vec<String>) -> Filter<'a>
+    {
+        Filter { field, values, negated: false, condition: Condition::Equal }
+    }
+
+    pub fn matching(field: &'a str, value: String, condition: Condition) -> Filter<'a>
+    {
+        Filter { field, values: vec![value], negated: false, condition }
+    }
+
+    pub fn negate(self) -> Filter<'a>
+    {
+        Filter { negated: !self.negated, ..self }
     }
 }
 
@@ -126,16 +269,3 @@ impl<'a> TagAssociationInput<'a> {
         TagAssociationInput { tag, resource }
     }
 }
-
-impl<'a> ListRecommendationsInput<'a> {
-    pub fn new(user: &'a str, limit: i32) -> ListRecommendationsInput<'a>
-    {
-        ListRecommendationsInput {
-            user,
-            limit,
-            context: RequestContext { 
-                scenario: "HOME"
-            }
-        }
-    }
-}
\ No newline at end of file
@@ -1,28 +1,44 @@
+pub mod aggregations;
+pub mod charts;
+pub mod collection;
+pub mod dashboards;
 pub mod datahub;
 pub mod datasets;
 pub mod graphql;
 pub mod paging;
+pub mod pipelines;
 pub mod platforms;
 pub mod requests;
+pub mod sort;
 pub mod tags;
 
+pub use aggregations::{Aggregations, Bucket, Facet};
+pub use charts::{Charts, ChartEnvelope};
+pub use collection::Collection;
+pub use dashboards::{Dashboards, DashboardEnvelope};
 pub use datahub::{
     CreateTag,
     DeleteTag,
+    ErrorResponse,
     QueryResponse,
     DatasetAddTagResponse,
-    ListRecommendationsResponse
+    CountResponse,
 };
-pub use datasets::{Datasets, DatasetEnvelope};
+pub use datasets::{Datasets, DatasetEnvelope, Projection};
 pub use graphql::{
     GraphQL,
     Variables,
     SearchInput,
+    ScrollInput,
     AutoCompleteInput,
+    RelatedInput,
     TagAssociationInput,
-    ListRecommendationsInput,
-    Filter
+    Condition,
+    Filter,
+    SortOrder,
 };
-pub use paging::Paging;
+pub use paging::{Links, Paging};
+pub use pipelines::{Pipelines, PipelineEnvelope, Jobs};
 pub use platforms::{Platforms, PlatformEnvelope};
+pub use sort::{Sort, SortKey};
 pub use tags::{Tags, Tag, TagEnvelope};
@@ -1,15 +1,39 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Paging {
     total: i32,
     limit: i32,
     offset: i32,
+
+    /// The next page, when there is one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+
+    /// The previous page, while it's within reach of `offset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev: Option<String>,
+}
+
+/// Where a page starts and the URLs of the pages either side of it.
+#[derive(Default)]
+pub struct Links {
+    pub offset: i32,
+    pub next: Option<String>,
+    pub prev: Option<String>,
 }
 
 impl Paging {
     pub fn new(offset: i32, limit: i32, total: i32) -> Paging
     {
-        Paging { total, limit, offset }
+        Paging { total, limit, offset, next: None, prev: None }
     }
-}
\ No newline at end of file
+
+    pub fn link(&mut self, links: &Links)
+    {
+        self.offset = links.offset;
+        self.next = links.next.clone();
+        self.prev = links.prev.clone();
+    }
+}
@@ -0,0 +1,204 @@
+use std::convert::From;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::schemas::{
+    collection::{name_key, Collection}, datahub, paging, Aggregations, Bucket, DatasetEnvelope, Links, Projection, SortKey, tags,
+};
+
+#[derive(Serialize, ToSchema)]
+pub struct Pipelines {
+    data: Vec<PipelineEnvelope>,
+    paging: Option<paging::Paging>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<Bucket>>>)]
+    aggregations: Option<Aggregations>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PipelineEnvelope {
+    pipeline: Option<Pipeline>
+}
+
+/// A DataHub data flow, e.g. an Airflow DAG.
+#[derive(Serialize, ToSchema)]
+pub struct Pipeline {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+
+    /// What runs the pipeline, e.g. airflow.
+    orchestrator: String,
+
+    cluster: Option<String>,
+    project: Option<String>,
+
+    /// The pipeline in its orchestrator.
+    url: Option<String>,
+
+    tags: Vec<tags::TagEnvelope>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Jobs {
+    data: Vec<JobEnvelope>,
+    paging: Option<paging::Paging>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JobEnvelope {
+    job: Option<Job>
+}
+
+/// A DataHub data job, one step of a pipeline, with the datasets it reads
+/// and writes.
+#[derive(Serialize, ToSchema)]
+pub struct Job {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    inputs: Vec<DatasetEnvelope>,
+    outputs: Vec<DatasetEnvelope>,
+}
+
+impl<'a> TryFrom<&datahub::Entity<'a>> for PipelineEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
+    {
+        match e {
+            datahub::Entity::DataFlow(flow) => Ok(PipelineEnvelope::from(flow)),
+            _ => Err(datahub::UnexpectedEntity)
+        }
+    }
+}
+
+impl<'a> From<&datahub::DataFlow<'a>> for PipelineEnvelope {
+    fn from(flow: &datahub::DataFlow) -> Self
+    {
+        PipelineEnvelope {
+            pipeline: Some(Pipeline::from(flow))
+        }
+    }
+}
+
+impl<'a> From<&datahub::DataFlow<'a>> for Pipeline {
+    fn from(e: &datahub::DataFlow) -> Self
+    {
+        Pipeline {
+            id: e.urn.to_owned(),
+            name: e.properties.as_ref()
+                .map(|p| p.name.to_string()),
+            description: e.properties.as_ref()
+                .and_then(|p| p.description.as_deref().map(str::to_owned)),
+            orchestrator: e.orchestrator.to_owned(),
+            cluster: e.cluster.map(str::to_owned),
+            project: e.properties.as_ref()
+                .and_then(|p| p.project.map(str::to_owned)),
+            url: e.properties.as_ref()
+                .and_then(|p| p.url.as_deref().map(str::to_owned)),
+            tags: e.tags.as_ref()
+                .map_or_else(Vec::new, |tags| tags.tags.iter()
+                    .map(tags::TagEnvelope::from)
+                    .collect()
+                ),
+        }
+    }
+}
+
+impl<'a> TryFrom<&datahub::Entity<'a>> for JobEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
+    {
+        match e {
+            datahub::Entity::DataJob(job) => Ok(JobEnvelope {
+                job: Some(Job::from(job))
+            }),
+            _ => Err(datahub::UnexpectedEntity)
+        }
+    }
+}
+
+impl<'a> From<&datahub::DataJob<'a>> for Job {
+    fn from(e: &datahub::DataJob) -> Self
+    {
+        let datasets = |datasets: Option<&Vec<datahub::Dataset>>| datasets
+            .map_or_else(Vec::new, |datasets| datasets.iter()
+                .map(DatasetEnvelope::from)
+                .collect()
+            );
+
+        Job {
+            id: e.urn.to_owned(),
+            name: e.properties.as_ref()
+                .map(|p| p.name.to_string()),
+            description: e.properties.as_ref()
+                .and_then(|p| p.description.as_deref().map(str::to_owned)),
+            url: e.properties.as_ref()
+                .and_then(|p| p.url.as_deref().map(str::to_owned)),
+            inputs: datasets(e.io.as_ref().and_then(|io| io.inputs.as_ref())),
+            outputs: datasets(e.io.as_ref().and_then(|io| io.outputs.as_ref())),
+        }
+    }
+}
+
+impl<'a> From<&datahub::QueryResponse<'a>> for Pipelines {
+    fn from(resp: &datahub::QueryResponse) -> Self
+    {
+        let (data, paging) = resp.process::<PipelineEnvelope>();
+        Pipelines { data, paging, aggregations: None }
+    }
+}
+
+impl<'a> From<&datahub::Relationships<'a>> for Jobs {
+    fn from(rels: &datahub::Relationships) -> Self
+    {
+        let data: Vec<_> = rels.relationships.iter()
+            .filter_map(|r| JobEnvelope::try_from(&r.entity).ok())
+            .collect();
+        let paging = paging::Paging::new(0, data.len() as i32, rels.total);
+
+        Jobs { data, paging: Some(paging) }
+    }
+}
+
+impl Collection for Pipelines {
+    type Item = PipelineEnvelope;
+
+    fn parts(&mut self) -> (&mut Vec<PipelineEnvelope>, &mut Option<paging::Paging>, &mut Option<Aggregations>)
+    {
+        (&mut self.data, &mut self.paging, &mut self.aggregations)
+    }
+
+    fn sort_key(e: &PipelineEnvelope, _: SortKey) -> Option<String>
+    {
+        e.pipeline.as_ref().and_then(|p| name_key(&p.id, p.name.as_ref()))
+    }
+}
+
+impl Jobs {
+    /// Points the paging block at the pages either side of this one.
+    pub fn link(mut self, links: &Links) -> Self
+    {
+        if let Some(paging) = self.paging.as_mut() {
+            paging.link(links);
+        }
+        self
+    }
+
+    /// Keeps just the projected fields of each job's datasets.
+    pub fn project(mut self, projection: Projection) -> Self
+    {
+        let project = |datasets: Vec<DatasetEnvelope>| datasets.into_iter()
+            .map(|ds| ds.project(projection))
+            .collect();
+        for job in self.data.iter_mut().filter_map(|e| e.job.as_mut()) {
+            job.inputs = project(std::mem::take(&mut job.inputs));
+            job.outputs = project(std::mem::take(&mut job.outputs));
+        }
+        self
+    }
+}
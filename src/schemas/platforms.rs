@@ -1,52 +1,108 @@
 use std::convert::From;
 use serde::Serialize;
+use utoipa::ToSchema;
 
-use crate::schemas::{datahub, paging};
+use crate::schemas::{datahub, paging, Links};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Platforms {
     data: Vec<PlatformEnvelope>,
     paging: Option<paging::Paging>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct PlatformEnvelope {
     pub platform: Platform
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Platform {
     pub id: String,
     pub name: String,
     pub title: String,
 
-    #[serde(rename(serialize = "type"))]
+    #[serde(rename = "type")]
     pub class: String,
+
+    /// URL of the platform's logo, when DataHub has one.
+    pub logo: Option<String>,
+
+    /// Number of datasets on the platform.
+    pub datasets: i64,
+}
+
+impl<'a> TryFrom<&datahub::Entity<'a>> for PlatformEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
+    {
+        match e {
+            datahub::Entity::DataPlatform(platform) => Ok(PlatformEnvelope::from(platform)),
+            _ => Err(datahub::UnexpectedEntity)
+        }
+    }
 }
 
-impl<'a> From<&datahub::PlatformEntity<'a>> for PlatformEnvelope {
-    fn from(e: &datahub::PlatformEntity) -> Self
+impl<'a> From<&datahub::DataPlatform<'a>> for PlatformEnvelope {
+    fn from(p: &datahub::DataPlatform) -> Self
     {
-        PlatformEnvelope { platform: Platform::from(e) }
+        PlatformEnvelope { platform: Platform::from(p) }
     }
 }
 
-impl<'a> From<&datahub::PlatformEntity<'a>> for Platform {
-    fn from(e: &datahub::PlatformEntity) -> Self
+impl<'a> From<&datahub::DataPlatform<'a>> for Platform {
+    fn from(p: &datahub::DataPlatform) -> Self
     {
         Platform {
-            id: e.entity.urn.to_owned(),
-            name: e.entity.name.to_owned(),
-            title: e.entity.properties.name.to_owned(),
-            class: e.entity.properties.class.to_owned(),
+            id: p.urn.to_owned(),
+            name: p.name.to_owned(),
+            title: p.properties.name.to_owned(),
+            class: p.properties.class.to_owned(),
+            logo: p.properties.logo.map(str::to_owned),
+            datasets: 0,
         }
     }
 }
 
-impl<'a> From<&datahub::ListRecommendationsResponse<'a>> for Platforms {
-    fn from(resp: &datahub::ListRecommendationsResponse) -> Self
+impl PlatformEnvelope {
+    /// Fills in the total of a dataset search filtered on the platform.
+    pub fn with_count(mut self, count: &datahub::CountResponse) -> Self
+    {
+        self.platform.datasets = count.total();
+        self
+    }
+}
+
+impl<'a> From<&datahub::QueryResponse<'a>> for Platforms {
+    fn from(resp: &datahub::QueryResponse) -> Self
     {
-        let (data, paging) = resp.process();
+        let (data, paging) = resp.process::<PlatformEnvelope>();
         Platforms { data, paging }
     }
-}
\ No newline at end of file
+}
+
+impl Platforms {
+    pub fn ids(&self) -> Vec<&str>
+    {
+        self.data.iter().map(|p| p.platform.id.as_str()).collect()
+    }
+
+    /// Fills in each platform's dataset count, given in the order of `ids`.
+    pub fn with_counts(mut self, counts: &[datahub::CountResponse]) -> Self
+    {
+        self.data = self.data.into_iter()
+            .zip(counts)
+            .map(|(p, count)| p.with_count(count))
+            .collect();
+        self
+    }
+
+    /// Points the paging block at the pages either side of this one.
+    pub fn link(mut self, links: &Links) -> Self
+    {
+        if let Some(paging) = self.paging.as_mut() {
+            paging.link(links);
+        }
+        self
+    }
+}
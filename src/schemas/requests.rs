@@ -1,13 +1,14 @@
 use serde::Deserialize;
+use utoipa::ToSchema;
 
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateTag {
     pub name: String,
     pub description: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct AddTag {
     pub tag: String,
 }
\ No newline at end of file
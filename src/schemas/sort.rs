@@ -0,0 +1,60 @@
+use std::str::FromStr;
+
+
+/// A `sort` parameter: the field to order results by, with a leading `-`
+/// for descending order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    LastModified,
+    Platform,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 3] = [SortKey::Name, SortKey::LastModified, SortKey::Platform];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            SortKey::Name         => "name",
+            SortKey::LastModified => "lastModified",
+            SortKey::Platform     => "platform",
+        }
+    }
+
+    /// The field DataHub sorts search results on.
+    pub fn field(&self) -> &'static str
+    {
+        match self {
+            SortKey::Name         => "_entityName",
+            SortKey::LastModified => "lastOperationTime",
+            SortKey::Platform     => "platform",
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let (name, descending) = match s.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (s, false)
+        };
+
+        SortKey::ALL.into_iter()
+            .find(|k| k.name() == name)
+            .map(|key| Sort { key, descending })
+            .ok_or_else(|| {
+                let names: Vec<_> = SortKey::ALL.iter().map(SortKey::name).collect();
+                format!("unknown sort {name}, expected one of {}", names.join(", "))
+            })
+    }
+}
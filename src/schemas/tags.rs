@@ -1,20 +1,25 @@
 use std::convert::From;
 use serde::Serialize;
+use utoipa::ToSchema;
 
-use crate::schemas::{datahub, paging};
+use crate::schemas::{collection::{name_key, Collection}, datahub, paging, Aggregations, Bucket, SortKey};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Tags {
     data: Vec<TagEnvelope>,
     paging: Option<paging::Paging>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<BTreeMap<String, Vec<Bucket>>>)]
+    aggregations: Option<Aggregations>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TagEnvelope {
     tag: Option<Tag>
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Tag {
     id: String,
     name: Option<String>,
@@ -28,16 +33,25 @@ impl Tag {
     }
 }
 
-impl<'a> From<&'a datahub::Entity<'a>> for TagEnvelope {
-    fn from(e: &datahub::Entity) -> Self
+impl TagEnvelope {
+    /// The tag's name, or its urn if it has none.
+    pub fn name(&self) -> Option<&str>
+    {
+        self.tag.as_ref().map(|tag| tag.name.as_deref().unwrap_or(&tag.id))
+    }
+}
+
+impl<'a> TryFrom<&'a datahub::Entity<'a>> for TagEnvelope {
+    type Error = datahub::UnexpectedEntity;
+
+    fn try_from(e: &datahub::Entity) -> Result<Self, Self::Error>
     {
         match e {
-            datahub::Entity::Tag(tag) => TagEnvelope { 
+            datahub::Entity::Tag(tag) => Ok(TagEnvelope { 
                 tag: Some(Tag::from(tag))
-            },
-            _ => panic!()
+            }),
+            _ => Err(datahub::UnexpectedEntity)
         }
-        
     }
 }
 
@@ -65,9 +79,9 @@ impl<'a> From<&datahub::Tag<'a>> for Tag {
         Tag {
             id: tag.urn.to_owned(),
             name: tag.properties.as_ref()
-                .map(|props| props.name.to_owned()),
+                .map(|props| props.name.to_string()),
             description: tag.properties.as_ref()
-                .map(|props| props.description.to_owned()),
+                .map(|props| props.description.to_string()),
         }
     }
 }
@@ -76,6 +90,20 @@ impl<'a> From<&datahub::QueryResponse<'a>> for Tags {
     fn from(resp: &datahub::QueryResponse) -> Self
     {
         let (data, paging) = resp.process::<TagEnvelope>();
-        Tags { data, paging }
+        Tags { data, paging, aggregations: None }
     }
-}
\ No newline at end of file
+}
+
+impl Collection for Tags {
+    type Item = TagEnvelope;
+
+    fn parts(&mut self) -> (&mut Vec<TagEnvelope>, &mut Option<paging::Paging>, &mut Option<Aggregations>)
+    {
+        (&mut self.data, &mut self.paging, &mut self.aggregations)
+    }
+
+    fn sort_key(e: &TagEnvelope, _: SortKey) -> Option<String>
+    {
+        e.tag.as_ref().and_then(|tag| name_key(&tag.id, tag.name.as_ref()))
+    }
+}
@@ -0,0 +1,91 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Instant};
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::{from_fn, FromFnLayer, Next},
+    response::Response,
+};
+use tracing::{field::Empty, Instrument};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+use crate::config::{LogConfig, LogFormat};
+use crate::metrics::METRICS;
+
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+#[derive(Clone)]
+pub struct RequestId(pub String);
+
+type BoxResponse = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+pub fn init(config: &LogConfig)
+{
+    // RUST_LOG, when set, takes precedence over the configured level.
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(&config.level));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(false).init(),
+    }
+}
+
+/// Tags the request with the caller's X-Request-Id, or a fresh one, and
+/// echoes it on the response.
+pub async fn request_id<B>(mut req: Request<B>, next: Next<B>) -> Response
+{
+    let id = req.headers().get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_owned);
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let span = tracing::info_span!("request", request_id = %id);
+    let mut resp = next.run(req).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    resp
+}
+
+/// Runs a route's handler in a span recording the route, the upstream
+/// operation and latency and the response status, and counts it in the
+/// request metrics.
+pub fn traced<B>(route: String)
+    -> FromFnLayer<impl Fn(Request<B>, Next<B>) -> BoxResponse + Clone + Send + Sync>
+    where B: Send + 'static
+{
+    let route: Arc<str> = route.into();
+    from_fn(move |req: Request<B>, next: Next<B>| {
+        let route = route.clone();
+        let method = req.method().clone();
+        let span = tracing::info_span!(
+            "handler",
+            route = &*route,
+            method = %method,
+            operation = Empty,
+            upstream_ms = Empty,
+            status = Empty,
+        );
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let in_flight = METRICS.started(&route, method.as_str());
+            let resp = next.run(req).instrument(span.clone()).await;
+            drop(in_flight);
+
+            span.record("status", resp.status().as_u16());
+            METRICS.handled(&route, method.as_str(), resp.status(), start.elapsed());
+            span.in_scope(|| {
+                tracing::info!(elapsed_ms = start.elapsed().as_millis() as u64, "handled")
+            });
+
+            resp
+        }) as BoxResponse
+    })
+}
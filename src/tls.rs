@@ -0,0 +1,193 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    Certificate,
+    ClientConfig,
+    PrivateKey,
+    RootCertStore,
+    ServerConfig,
+    server::AllowAnyAuthenticatedClient,
+};
+use rustls_pemfile::Item;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+use crate::config::{ConfigError, ListenerTlsConfig, UpstreamTlsConfig};
+
+
+/// The TLS settings for connections to DataHub: the system roots and/or
+/// a CA bundle, plus a client certificate when DataHub requires mTLS.
+pub fn client_config(config: &UpstreamTlsConfig) -> Result<ClientConfig, ConfigError>
+{
+    let mut roots = RootCertStore::empty();
+    if config.native_roots {
+        let native = rustls_native_certs::load_native_certs()
+            .map_err(|e| ConfigError::Read("system certificate store".into(), e))?;
+        for cert in native {
+            // The system store may hold certificates rustls can't use.
+            let _ = roots.add(&Certificate(cert.0));
+        }
+    }
+    if let Some(path) = &config.ca_bundle {
+        for cert in certs(path, "datahub.tls.ca_bundle")? {
+            roots.add(&cert).map_err(|e| invalid("datahub.tls.ca_bundle", path, e.to_string()))?;
+        }
+    }
+
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+
+    match (&config.client_cert, &config.client_key) {
+        (Some(cert), Some(key)) => {
+            let chain = certs(cert, "datahub.tls.client_cert")?;
+            let key_der = private_key(key, "datahub.tls.client_key")?;
+            builder.with_client_auth_cert(chain, key_der)
+                .map_err(|e| invalid("datahub.tls.client_cert", cert, e.to_string()))
+        },
+        _ => Ok(builder.with_no_client_auth())
+    }
+}
+
+/// The TLS settings for the service's own listener. Callers must present
+/// a certificate issued by `client_ca` when one is configured.
+pub fn server_config(config: &ListenerTlsConfig) -> Result<ServerConfig, ConfigError>
+{
+    let chain = certs(&config.cert, "server.tls.cert")?;
+    let key = private_key(&config.key, "server.tls.key")?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in certs(path, "server.tls.client_ca")? {
+                roots.add(&cert).map_err(|e| invalid("server.tls.client_ca", path, e.to_string()))?;
+            }
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        },
+        None => builder.with_no_client_auth()
+    };
+
+    let mut server = builder.with_single_cert(chain, key)
+        .map_err(|e| invalid("server.tls.key", &config.key, e.to_string()))?;
+    server.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(server)
+}
+
+/// Swaps in fresh certificates on SIGHUP, where there is one, or when one
+/// of the files changes. Established connections keep the certificates
+/// they were accepted with.
+pub async fn reload(config: ListenerTlsConfig, rustls: RustlsConfig)
+{
+    let mut hangup = Hangup::new();
+    let mut ticks = tokio::time::interval(Duration::from_secs(config.reload_interval.max(1)));
+    let mut stamp = modified(&config);
+
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => {},
+            _ = ticks.tick() => if modified(&config) == stamp { continue },
+        }
+
+        stamp = modified(&config);
+        match server_config(&config) {
+            Ok(server) => {
+                rustls.reload_from_config(Arc::new(server));
+                tracing::info!("reloaded TLS certificates");
+            },
+            Err(err) => tracing::warn!(error = %err, "keeping the current TLS certificates"),
+        }
+    }
+}
+
+// SIGHUP on Unix. Elsewhere, or when it can't be listened for, only the
+// file checks trigger a reload.
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<Signal>,
+}
+
+impl Hangup {
+    fn new() -> Hangup
+    {
+        #[cfg(unix)]
+        let signal = signal(SignalKind::hangup())
+            .map_err(|err| tracing::warn!(error = %err, "unable to listen for SIGHUP"))
+            .ok();
+
+        Hangup {
+            #[cfg(unix)]
+            signal,
+        }
+    }
+
+    async fn recv(&mut self)
+    {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            signal.recv().await;
+            return
+        }
+
+        std::future::pending().await
+    }
+}
+
+fn modified(config: &ListenerTlsConfig) -> Vec<Option<SystemTime>>
+{
+    [Some(&config.cert), Some(&config.key), config.client_ca.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Every certificate in a PEM file.
+pub fn certs(path: &Path, key: &'static str) -> Result<Vec<Certificate>, ConfigError>
+{
+    let certs: Vec<_> = read_pem(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None
+        })
+        .collect();
+
+    if certs.is_empty() {
+        Err(invalid(key, path, "no certificates found".into()))
+    } else {
+        Ok(certs)
+    }
+}
+
+/// The first private key in a PEM file.
+pub fn private_key(path: &Path, key: &'static str) -> Result<PrivateKey, ConfigError>
+{
+    read_pem(path)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None
+        })
+        .ok_or_else(|| invalid(key, path, "no private key found".into()))
+}
+
+fn read_pem(path: &Path) -> Result<Vec<Item>, ConfigError>
+{
+    let file = File::open(path).map_err(|e| ConfigError::Read(path.to_owned(), e))?;
+    rustls_pemfile::read_all(&mut BufReader::new(file))
+        .map_err(|e| ConfigError::Read(path.to_owned(), e))
+}
+
+fn invalid(key: &'static str, path: &Path, reason: String) -> ConfigError
+{
+    ConfigError::Invalid { key, value: path.display().to_string(), reason }
+}